@@ -1,4 +1,10 @@
-use crate::{ClusterInfo, EnumeratedValues, Peripheral, RegisterInfo, RegisterProperties};
+use std::collections::HashSet;
+
+use crate::error::*;
+use crate::{
+    ClusterInfo, Device, EnumeratedValues, Field, FieldInfo, PeripheralInfo, RegisterCluster,
+    RegisterInfo, RegisterProperties,
+};
 
 /// Fill empty fields of structure with values of other structure
 pub trait DeriveFrom {
@@ -25,6 +31,7 @@ impl DeriveFrom for ClusterInfo {
 impl DeriveFrom for EnumeratedValues {
     fn derive_from(&self, other: &Self) -> Self {
         let mut derived = self.clone();
+        derived.header_enum_name = derived.header_enum_name.or(other.header_enum_name.clone());
         derived.usage = derived.usage.or(other.usage.clone());
         if derived.values.is_empty() {
             derived.values = other.values.clone();
@@ -33,14 +40,42 @@ impl DeriveFrom for EnumeratedValues {
     }
 }
 
-impl DeriveFrom for Peripheral {
+impl DeriveFrom for FieldInfo {
+    fn derive_from(&self, other: &Self) -> Self {
+        let mut derived = self.clone();
+        derived.description = derived.description.or(other.description.clone());
+        derived.access = derived.access.or(other.access);
+        if derived.enumerated_values.is_empty() {
+            derived.enumerated_values = other.enumerated_values.clone();
+        }
+        derived.write_constraint = derived.write_constraint.or(other.write_constraint);
+        derived.modified_write_values = derived
+            .modified_write_values
+            .or(other.modified_write_values);
+        derived.read_action = derived.read_action.or(other.read_action);
+        derived
+    }
+}
+
+impl DeriveFrom for PeripheralInfo {
     fn derive_from(&self, other: &Self) -> Self {
         let mut derived = self.clone();
         derived.group_name = derived.group_name.or(other.group_name.clone());
         derived.description = derived.description.or(other.description.clone());
+        derived.prepend_to_name = derived.prepend_to_name.or(other.prepend_to_name.clone());
+        derived.append_to_name = derived.append_to_name.or(other.append_to_name.clone());
+        derived.header_struct_name = derived
+            .header_struct_name
+            .or(other.header_struct_name.clone());
         derived.default_register_properties = derived
             .default_register_properties
             .derive_from(&other.default_register_properties);
+        derived.disable_condition = derived
+            .disable_condition
+            .or(other.disable_condition.clone());
+        if derived.address_block.is_empty() {
+            derived.address_block = other.address_block.clone();
+        }
         derived.registers = derived.registers.or(other.registers.clone());
         if derived.interrupt.is_empty() {
             derived.interrupt = other.interrupt.clone();
@@ -54,7 +89,9 @@ impl DeriveFrom for RegisterInfo {
         let mut derived = self.clone();
         derived.description = derived.description.or(other.description.clone());
         derived.size = derived.size.or(other.size);
+        derived.data_type = derived.data_type.or(other.data_type);
         derived.access = derived.access.or(other.access);
+        derived.protection = derived.protection.or(other.protection);
         derived.reset_value = derived.reset_value.or(other.reset_value);
         derived.reset_mask = derived.reset_mask.or(other.reset_mask);
         derived.fields = derived.fields.or(other.fields.clone());
@@ -62,17 +99,509 @@ impl DeriveFrom for RegisterInfo {
         derived.modified_write_values = derived
             .modified_write_values
             .or(other.modified_write_values);
+        derived.read_action = derived.read_action.or(other.read_action);
         derived
     }
 }
 
 impl DeriveFrom for RegisterProperties {
     fn derive_from(&self, other: &Self) -> Self {
-        let mut derived = self.clone();
-        derived.size = derived.size.or(other.size);
-        derived.reset_value = derived.reset_value.or(other.reset_value);
-        derived.reset_mask = derived.reset_mask.or(other.reset_mask);
-        derived.access = derived.access.or(other.access);
-        derived
+        self.inherit(other)
+    }
+}
+
+/// Resolves every `derivedFrom` reference of a device
+///
+/// Targets are looked up by name, first among the siblings of the derived element and then
+/// in each enclosing scope up to the device, so both `REG` and dotted paths such as
+/// `PERIPH.CLUSTER.REG` or `PERIPH.REG.FIELD` are accepted. A single `enumeratedValues` name
+/// is also searched in the other fields of the same register.
+///
+/// Targets are resolved before the elements derived from them. Dangling and cyclic references
+/// are reported as `DeriveError`s.
+pub fn resolve(device: &Device) -> Result<Device> {
+    let mut resolver = Resolver {
+        device: device.clone(),
+        done: HashSet::new(),
+        stack: Vec::new(),
+    };
+    for p in 0..resolver.device.peripherals.len() {
+        resolver.resolve(Loc {
+            peripheral: p,
+            ..Loc::default()
+        })?;
+    }
+    Ok(resolver.device)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Kind {
+    Peripheral,
+    Cluster,
+    Register,
+    Field,
+    EnumeratedValues,
+}
+
+/// Location of an element in the device tree, as indices
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+struct Loc {
+    peripheral: usize,
+    /// Indices of nested registers and clusters
+    children: Vec<usize>,
+    field: Option<usize>,
+    values: Option<usize>,
+}
+
+impl Loc {
+    /// Location of the enclosing element, `None` for peripherals
+    fn parent(&self) -> Option<Loc> {
+        let mut parent = self.clone();
+        if parent.values.take().is_some() || parent.field.take().is_some() {
+            return Some(parent);
+        }
+        parent.children.pop().map(|_| parent)
+    }
+}
+
+struct Resolver {
+    device: Device,
+    /// Elements whose derivation and children are resolved
+    done: HashSet<Loc>,
+    /// Elements whose derivation is being resolved
+    stack: Vec<Loc>,
+}
+
+impl Resolver {
+    fn resolve(&mut self, loc: Loc) -> Result<()> {
+        if self.done.contains(&loc) {
+            return Ok(());
+        }
+        if let Some(pos) = self.stack.iter().position(|l| *l == loc) {
+            let mut chain: Vec<String> = self.stack[pos..].iter().map(|l| self.path(l)).collect();
+            chain.push(self.path(&loc));
+            return Err(DeriveError::Cyclic(chain).into());
+        }
+
+        if let Some(target) = self.derived_from(&loc) {
+            let base = self
+                .lookup(&loc, &target)
+                .ok_or_else(|| DeriveError::NotFound(self.path(&loc), target.clone()))?;
+            self.stack.push(loc.clone());
+            self.resolve(base.clone())?;
+            self.stack.pop();
+            self.apply(&loc, &base);
+        }
+
+        for child in self.children(&loc) {
+            self.resolve(child)?;
+        }
+        self.done.insert(loc);
+        Ok(())
+    }
+
+    /// Replaces the element at `loc` with its derivation from the element at `base`
+    fn apply(&mut self, loc: &Loc, base: &Loc) {
+        match self.kind(loc) {
+            Kind::Peripheral => {
+                let other = (*self.device.peripherals[base.peripheral]).clone();
+                let p = &mut self.device.peripherals[loc.peripheral];
+                **p = p.derive_from(&other);
+            }
+            Kind::Cluster => {
+                if let Some(RegisterCluster::Cluster(other)) = self.register_cluster(base) {
+                    let other = (**other).clone();
+                    if let Some(RegisterCluster::Cluster(c)) = self.register_cluster_mut(loc) {
+                        **c = c.derive_from(&other);
+                    }
+                }
+            }
+            Kind::Register => {
+                if let Some(RegisterCluster::Register(other)) = self.register_cluster(base) {
+                    let other = (**other).clone();
+                    if let Some(RegisterCluster::Register(r)) = self.register_cluster_mut(loc) {
+                        **r = r.derive_from(&other);
+                    }
+                }
+            }
+            Kind::Field => {
+                if let Some(other) = self.field(base).map(|f| (**f).clone()) {
+                    if let Some(f) = self.field_mut(loc) {
+                        **f = f.derive_from(&other);
+                    }
+                }
+            }
+            Kind::EnumeratedValues => {
+                if let Some(other) = self.values(base).cloned() {
+                    if let Some(ev) = self.values_mut(loc) {
+                        *ev = ev.derive_from(&other);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Finds the element of the same kind as `loc` named by `target`
+    ///
+    /// Other elements are preferred, `loc` itself is returned when it is the only match so
+    /// that the self-reference is reported as a cycle.
+    fn lookup(&self, loc: &Loc, target: &str) -> Option<Loc> {
+        let path: Vec<&str> = target.split('.').collect();
+        let kind = self.kind(loc);
+        let mut scope = loc.parent();
+        let mut itself = false;
+        loop {
+            if let Some(found) = self.find(scope.as_ref(), &path) {
+                if found == *loc {
+                    itself = true;
+                } else if self.kind(&found) == kind {
+                    return Some(found);
+                }
+            }
+            if kind == Kind::EnumeratedValues && path.len() == 1 {
+                if let Some(found) = self.find_in_sibling_fields(loc, path[0]) {
+                    return Some(found);
+                }
+            }
+            match scope {
+                Some(s) => scope = s.parent(),
+                None if itself => return Some(loc.clone()),
+                None => return None,
+            }
+        }
+    }
+
+    /// Walks `path` down from `scope`, the device itself when `None`
+    fn find(&self, scope: Option<&Loc>, path: &[&str]) -> Option<Loc> {
+        let mut current = scope.cloned();
+        for name in path {
+            current = Some(self.child(current.as_ref(), name)?);
+        }
+        current
+    }
+
+    fn child(&self, scope: Option<&Loc>, name: &str) -> Option<Loc> {
+        let scope = match scope {
+            None => {
+                let p = self
+                    .device
+                    .peripherals
+                    .iter()
+                    .position(|p| p.name == name)?;
+                return Some(Loc {
+                    peripheral: p,
+                    ..Loc::default()
+                });
+            }
+            Some(scope) => scope,
+        };
+        self.children(scope)
+            .into_iter()
+            .find(|c| self.name(c).as_deref() == Some(name))
+    }
+
+    fn find_in_sibling_fields(&self, loc: &Loc, name: &str) -> Option<Loc> {
+        let register = loc.parent()?.parent()?;
+        self.children(&register)
+            .into_iter()
+            .flat_map(|f| self.children(&f))
+            .find(|c| c != loc && self.name(c).as_deref() == Some(name))
+    }
+
+    fn children(&self, loc: &Loc) -> Vec<Loc> {
+        let kind = self.kind(loc);
+        let n = match kind {
+            Kind::Peripheral => self.device.peripherals[loc.peripheral]
+                .registers
+                .as_ref()
+                .map_or(0, Vec::len),
+            Kind::Cluster | Kind::Register => match self.register_cluster(loc) {
+                Some(RegisterCluster::Cluster(c)) => c.children.len(),
+                Some(RegisterCluster::Register(r)) => r.fields.as_ref().map_or(0, Vec::len),
+                None => 0,
+            },
+            Kind::Field => self.field(loc).map_or(0, |f| f.enumerated_values.len()),
+            Kind::EnumeratedValues => 0,
+        };
+        (0..n)
+            .map(|i| {
+                let mut child = loc.clone();
+                match kind {
+                    Kind::Peripheral | Kind::Cluster => child.children.push(i),
+                    Kind::Register => child.field = Some(i),
+                    _ => child.values = Some(i),
+                }
+                child
+            })
+            .collect()
+    }
+
+    fn kind(&self, loc: &Loc) -> Kind {
+        if loc.values.is_some() {
+            Kind::EnumeratedValues
+        } else if loc.field.is_some() {
+            Kind::Field
+        } else if loc.children.is_empty() {
+            Kind::Peripheral
+        } else if let Some(RegisterCluster::Cluster(_)) = self.register_cluster(loc) {
+            Kind::Cluster
+        } else {
+            Kind::Register
+        }
+    }
+
+    fn name(&self, loc: &Loc) -> Option<String> {
+        match self.kind(loc) {
+            Kind::Peripheral => Some(self.device.peripherals[loc.peripheral].name.clone()),
+            Kind::Cluster | Kind::Register => match self.register_cluster(loc)? {
+                RegisterCluster::Cluster(c) => Some(c.name.clone()),
+                RegisterCluster::Register(r) => Some(r.name.clone()),
+            },
+            Kind::Field => self.field(loc).map(|f| f.name.clone()),
+            Kind::EnumeratedValues => self.values(loc)?.name.clone(),
+        }
+    }
+
+    fn derived_from(&self, loc: &Loc) -> Option<String> {
+        match self.kind(loc) {
+            Kind::Peripheral => self.device.peripherals[loc.peripheral].derived_from.clone(),
+            Kind::Cluster | Kind::Register => match self.register_cluster(loc)? {
+                RegisterCluster::Cluster(c) => c.derived_from.clone(),
+                RegisterCluster::Register(r) => r.derived_from.clone(),
+            },
+            Kind::Field => self.field(loc)?.derived_from.clone(),
+            Kind::EnumeratedValues => self.values(loc)?.derived_from.clone(),
+        }
+    }
+
+    /// Dotted name path of an element, used in error messages
+    fn path(&self, loc: &Loc) -> String {
+        let mut names = Vec::new();
+        let mut current = Some(loc.clone());
+        while let Some(l) = current {
+            names.push(
+                self.name(&l)
+                    .unwrap_or_else(|| format!("#{}", l.values.unwrap_or_default())),
+            );
+            current = l.parent();
+        }
+        names.reverse();
+        names.join(".")
+    }
+
+    fn register_cluster(&self, loc: &Loc) -> Option<&RegisterCluster> {
+        let (last, path) = loc.children.split_last()?;
+        let mut list = self.device.peripherals[loc.peripheral].registers.as_ref()?;
+        for &i in path {
+            match list.get(i)? {
+                RegisterCluster::Cluster(c) => list = &c.children,
+                RegisterCluster::Register(_) => return None,
+            }
+        }
+        list.get(*last)
+    }
+
+    fn register_cluster_mut(&mut self, loc: &Loc) -> Option<&mut RegisterCluster> {
+        let (last, path) = loc.children.split_last()?;
+        let mut list = self.device.peripherals[loc.peripheral].registers.as_mut()?;
+        for &i in path {
+            match list.get_mut(i)? {
+                RegisterCluster::Cluster(c) => list = &mut c.children,
+                RegisterCluster::Register(_) => return None,
+            }
+        }
+        list.get_mut(*last)
+    }
+
+    fn field(&self, loc: &Loc) -> Option<&Field> {
+        match self.register_cluster(loc)? {
+            RegisterCluster::Register(r) => r.fields.as_ref()?.get(loc.field?),
+            RegisterCluster::Cluster(_) => None,
+        }
+    }
+
+    fn field_mut(&mut self, loc: &Loc) -> Option<&mut Field> {
+        let i = loc.field?;
+        match self.register_cluster_mut(loc)? {
+            RegisterCluster::Register(r) => r.fields.as_mut()?.get_mut(i),
+            RegisterCluster::Cluster(_) => None,
+        }
+    }
+
+    fn values(&self, loc: &Loc) -> Option<&EnumeratedValues> {
+        self.field(loc)?.enumerated_values.get(loc.values?)
+    }
+
+    fn values_mut(&mut self, loc: &Loc) -> Option<&mut EnumeratedValues> {
+        let i = loc.values?;
+        self.field_mut(loc)?.enumerated_values.get_mut(i)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(peripherals: &str) -> Device {
+        crate::parse(&format!(
+            "<device><name>DEV</name><peripherals>{}</peripherals></device>",
+            peripherals
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn resolve_references() {
+        let d = device(
+            "
+            <peripheral derivedFrom=\"TIM1\">
+                <name>TIM2</name>
+                <baseAddress>0x40001000</baseAddress>
+            </peripheral>
+            <peripheral>
+                <name>TIM1</name>
+                <description>Timer</description>
+                <disableCondition>RCC->APB1ENR.TIM1EN == 0</disableCondition>
+                <baseAddress>0x40000000</baseAddress>
+                <addressBlock>
+                    <offset>0</offset>
+                    <size>0x400</size>
+                    <usage>registers</usage>
+                </addressBlock>
+                <registers>
+                    <register derivedFrom=\"CR1\">
+                        <name>CR2</name>
+                        <addressOffset>0x4</addressOffset>
+                    </register>
+                    <register>
+                        <name>CR1</name>
+                        <addressOffset>0x0</addressOffset>
+                        <size>16</size>
+                        <fields>
+                            <field>
+                                <name>EN</name>
+                                <bitOffset>0</bitOffset>
+                                <bitWidth>1</bitWidth>
+                                <enumeratedValues>
+                                    <name>ENABLE</name>
+                                    <enumeratedValue>
+                                        <name>ON</name>
+                                        <value>1</value>
+                                    </enumeratedValue>
+                                </enumeratedValues>
+                            </field>
+                            <field>
+                                <name>MODE</name>
+                                <bitOffset>1</bitOffset>
+                                <bitWidth>1</bitWidth>
+                                <enumeratedValues derivedFrom=\"ENABLE\">
+                                </enumeratedValues>
+                            </field>
+                        </fields>
+                    </register>
+                    <cluster>
+                        <name>CH</name>
+                        <addressOffset>0x10</addressOffset>
+                        <register>
+                            <name>CCR</name>
+                            <addressOffset>0x0</addressOffset>
+                            <fields>
+                                <field derivedFrom=\"TIM1.CR1.EN\">
+                                    <name>EN</name>
+                                    <bitOffset>0</bitOffset>
+                                    <bitWidth>1</bitWidth>
+                                </field>
+                            </fields>
+                        </register>
+                    </cluster>
+                </registers>
+            </peripheral>
+            ",
+        );
+
+        let d = resolve(&d).unwrap();
+        let tim2 = &d.peripherals[0];
+        assert_eq!(tim2.description, Some(String::from("Timer")));
+        assert_eq!(tim2.address_block, d.peripherals[1].address_block);
+        assert_eq!(
+            tim2.disable_condition,
+            Some(String::from("RCC->APB1ENR.TIM1EN == 0"))
+        );
+        let registers = tim2.registers.as_ref().unwrap();
+        let cr2 = match &registers[0] {
+            RegisterCluster::Register(r) => r,
+            _ => panic!(),
+        };
+        assert_eq!(cr2.size, Some(16));
+        let fields = cr2.fields.as_ref().unwrap();
+        assert_eq!(fields[1].enumerated_values[0].values[0].name, "ON");
+        let ccr = match &registers[2] {
+            RegisterCluster::Cluster(c) => match &c.children[0] {
+                RegisterCluster::Register(r) => r,
+                _ => panic!(),
+            },
+            _ => panic!(),
+        };
+        let en = &ccr.fields.as_ref().unwrap()[0];
+        assert_eq!(en.enumerated_values[0].name, Some(String::from("ENABLE")));
+    }
+
+    #[test]
+    fn dangling_and_cyclic() {
+        let d = device(
+            "
+            <peripheral derivedFrom=\"UART\">
+                <name>USART</name>
+                <baseAddress>0x40000000</baseAddress>
+            </peripheral>
+            ",
+        );
+        let err = resolve(&d).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeriveError>(),
+            Some(&DeriveError::NotFound("USART".into(), "UART".into()))
+        );
+
+        let d = device(
+            "
+            <peripheral>
+                <name>P</name>
+                <baseAddress>0x40000000</baseAddress>
+                <registers>
+                    <register derivedFrom=\"B\">
+                        <name>A</name>
+                        <addressOffset>0x0</addressOffset>
+                    </register>
+                    <register derivedFrom=\"P.A\">
+                        <name>B</name>
+                        <addressOffset>0x4</addressOffset>
+                    </register>
+                </registers>
+            </peripheral>
+            ",
+        );
+        let err = resolve(&d).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeriveError>(),
+            Some(&DeriveError::Cyclic(vec![
+                "P.A".into(),
+                "P.B".into(),
+                "P.A".into()
+            ]))
+        );
+
+        let d = device(
+            "
+            <peripheral derivedFrom=\"A\">
+                <name>A</name>
+                <baseAddress>0x40000000</baseAddress>
+            </peripheral>
+            ",
+        );
+        let err = resolve(&d).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<DeriveError>(),
+            Some(&DeriveError::Cyclic(vec!["A".into(), "A".into()]))
+        );
     }
 }
@@ -0,0 +1,474 @@
+//! Disable conditions.
+//! Parses the C expressions of `disableCondition` and evaluates them against register values
+
+use core::convert::TryFrom;
+
+use crate::error::*;
+use crate::index::DeviceIndex;
+
+/// A `disableCondition` with its registers and fields resolved against a device
+///
+/// The supported subset of C has integer literals, `||`, `&&`, `|`, `^`, `&`, comparisons,
+/// shifts, `!`, `~` and parentheses. Registers are written `PERIPH->REG`, with enclosing
+/// clusters as in `PERIPH->CLUSTER.REG`, and fields `PERIPH->REG.FIELD`. Values are
+/// computed on 64 bits.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    expr: Expr,
+}
+
+/// Source of register values to evaluate conditions against
+pub trait RegisterValues {
+    /// Reads the register of `size` bits at `address`
+    fn read(&mut self, address: u64, size: u32) -> Result<u64>;
+}
+
+impl<F> RegisterValues for F
+where
+    F: FnMut(u64, u32) -> Result<u64>,
+{
+    fn read(&mut self, address: u64, size: u32) -> Result<u64> {
+        self(address, size)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    Or,
+    And,
+    BitOr,
+    BitXor,
+    BitAnd,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Shl,
+    Shr,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Expr {
+    Value(u64),
+    Register {
+        address: u64,
+        size: u32,
+    },
+    Field {
+        address: u64,
+        size: u32,
+        offset: u32,
+        width: u32,
+    },
+    Not(Box<Expr>),
+    Complement(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(u64),
+    Ident(String),
+    Punct(&'static str),
+}
+
+/// Binary operators from the loosest to the tightest binding
+const PRECEDENCE: &[&[(&str, Op)]] = &[
+    &[("||", Op::Or)],
+    &[("&&", Op::And)],
+    &[("|", Op::BitOr)],
+    &[("^", Op::BitXor)],
+    &[("&", Op::BitAnd)],
+    &[("==", Op::Eq), ("!=", Op::Ne)],
+    &[("<=", Op::Le), (">=", Op::Ge), ("<", Op::Lt), (">", Op::Gt)],
+    &[("<<", Op::Shl), (">>", Op::Shr)],
+];
+
+/// Deepest nesting of parentheses and operators accepted, keeping recursion bounded
+const MAX_DEPTH: usize = 256;
+
+/// Punctuation, longest first
+const PUNCTS: &[&str] = &[
+    "||", "&&", "==", "!=", "<=", ">=", "<<", ">>", "->", "|", "^", "&", "<", ">", "!", "~", "(",
+    ")", ".",
+];
+
+impl Condition {
+    /// Parses `text` and resolves its registers and fields with `index`
+    ///
+    /// Expressions nested more than 256 levels deep are rejected.
+    pub fn parse(text: &str, index: &DeviceIndex) -> Result<Condition> {
+        let mut parser = Parser {
+            text,
+            tokens: tokenize(text)?,
+            pos: 0,
+            depth: 0,
+            index,
+        };
+        let expr = parser.binary(0)?;
+        if parser.pos < parser.tokens.len() {
+            return Err(parser.syntax("unexpected trailing tokens").into());
+        }
+        Ok(Condition { expr })
+    }
+
+    /// Whether the condition holds, meaning that the peripheral must not be accessed
+    pub fn evaluate(&self, values: &mut dyn RegisterValues) -> Result<bool> {
+        Ok(eval(&self.expr, values)? != 0)
+    }
+}
+
+fn eval(expr: &Expr, values: &mut dyn RegisterValues) -> Result<u64> {
+    Ok(match expr {
+        Expr::Value(v) => *v,
+        Expr::Register { address, size } => values.read(*address, *size)?,
+        Expr::Field {
+            address,
+            size,
+            offset,
+            width,
+        } => {
+            let value = values.read(*address, *size)?;
+            let mask = if *width >= 64 {
+                u64::MAX
+            } else {
+                (1 << width) - 1
+            };
+            value.checked_shr(*offset).unwrap_or(0) & mask
+        }
+        Expr::Not(e) => (eval(e, values)? == 0) as u64,
+        Expr::Complement(e) => !eval(e, values)?,
+        Expr::Binary(Op::Or, l, r) => (eval(l, values)? != 0 || eval(r, values)? != 0) as u64,
+        Expr::Binary(Op::And, l, r) => (eval(l, values)? != 0 && eval(r, values)? != 0) as u64,
+        Expr::Binary(op, l, r) => {
+            let (l, r) = (eval(l, values)?, eval(r, values)?);
+            match op {
+                Op::BitOr => l | r,
+                Op::BitXor => l ^ r,
+                Op::BitAnd => l & r,
+                Op::Eq => (l == r) as u64,
+                Op::Ne => (l != r) as u64,
+                Op::Lt => (l < r) as u64,
+                Op::Le => (l <= r) as u64,
+                Op::Gt => (l > r) as u64,
+                Op::Ge => (l >= r) as u64,
+                Op::Shl => u32::try_from(r)
+                    .ok()
+                    .and_then(|r| l.checked_shl(r))
+                    .unwrap_or(0),
+                Op::Shr => u32::try_from(r)
+                    .ok()
+                    .and_then(|r| l.checked_shr(r))
+                    .unwrap_or(0),
+                Op::Or | Op::And => unreachable!(),
+            }
+        }
+    })
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let syntax = |detail: &str| ConditionError::Syntax(text.to_string(), detail.to_string());
+    let mut tokens = Vec::new();
+    let mut rest = text.trim_start();
+    while let Some(c) = rest.chars().next() {
+        let len = if c.is_ascii_digit() {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            // Drop C integer suffixes such as `U` or `UL`
+            let literal = rest[..len].trim_end_matches(&['u', 'U', 'l', 'L'][..]);
+            let value = if let Some(hex) = literal
+                .strip_prefix("0x")
+                .or_else(|| literal.strip_prefix("0X"))
+            {
+                u64::from_str_radix(hex, 16)
+            } else if let Some(bin) = literal
+                .strip_prefix("0b")
+                .or_else(|| literal.strip_prefix("0B"))
+            {
+                u64::from_str_radix(bin, 2)
+            } else {
+                literal.parse()
+            };
+            let value = value.map_err(|_| syntax(&format!("invalid number `{}`", &rest[..len])))?;
+            tokens.push(Token::Number(value));
+            len
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..len].to_string()));
+            len
+        } else {
+            let punct = PUNCTS
+                .iter()
+                .find(|p| rest.starts_with(*p))
+                .ok_or_else(|| syntax(&format!("unexpected character `{}`", c)))?;
+            tokens.push(Token::Punct(punct));
+            punct.len()
+        };
+        rest = rest[len..].trim_start();
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<Token>,
+    pos: usize,
+    /// Nesting of the expression being parsed, see `MAX_DEPTH`
+    depth: usize,
+    index: &'a DeviceIndex<'a>,
+}
+
+impl<'a> Parser<'a> {
+    fn syntax(&self, detail: &str) -> ConditionError {
+        ConditionError::Syntax(self.text.to_string(), detail.to_string())
+    }
+
+    fn peek_punct(&self) -> Option<&'static str> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Punct(p)) => Some(p),
+            _ => None,
+        }
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        if self.peek_punct() == Some(punct) {
+            self.pos += 1;
+            Ok(())
+        } else {
+            Err(self.syntax(&format!("expected `{}`", punct)).into())
+        }
+    }
+
+    /// Enters one more level of nesting
+    fn nest(&mut self) -> Result<()> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.syntax("expression nested too deeply").into());
+        }
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Ident(name)) => {
+                self.pos += 1;
+                Ok(name.clone())
+            }
+            _ => Err(self.syntax("expected a name").into()),
+        }
+    }
+
+    /// Parses binary operations of `level` and tighter
+    fn binary(&mut self, level: usize) -> Result<Expr> {
+        if level == PRECEDENCE.len() {
+            return self.unary();
+        }
+        let mut expr = self.binary(level + 1)?;
+        // Each operator of a chain nests the expression built so far one level deeper
+        let depth = self.depth;
+        while let Some(&(_, op)) = self
+            .peek_punct()
+            .and_then(|p| PRECEDENCE[level].iter().find(|(s, _)| *s == p))
+        {
+            self.pos += 1;
+            self.nest()?;
+            let rhs = self.binary(level + 1)?;
+            expr = Expr::Binary(op, Box::new(expr), Box::new(rhs));
+        }
+        self.depth = depth;
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        match self.peek_punct() {
+            Some("!") => {
+                self.pos += 1;
+                self.nest()?;
+                let expr = self.unary()?;
+                self.depth -= 1;
+                Ok(Expr::Not(Box::new(expr)))
+            }
+            Some("~") => {
+                self.pos += 1;
+                self.nest()?;
+                let expr = self.unary()?;
+                self.depth -= 1;
+                Ok(Expr::Complement(Box::new(expr)))
+            }
+            Some("(") => {
+                self.pos += 1;
+                self.nest()?;
+                let expr = self.binary(0)?;
+                self.expect(")")?;
+                self.depth -= 1;
+                Ok(expr)
+            }
+            _ => match self.tokens.get(self.pos) {
+                Some(Token::Number(v)) => {
+                    self.pos += 1;
+                    Ok(Expr::Value(*v))
+                }
+                Some(Token::Ident(_)) => self.reference(),
+                _ => Err(self.syntax("expected a value").into()),
+            },
+        }
+    }
+
+    /// Parses and resolves `PERIPH->REG` or `PERIPH->REG.FIELD`
+    fn reference(&mut self) -> Result<Expr> {
+        let mut path = vec![self.ident()?];
+        self.expect("->")?;
+        path.push(self.ident()?);
+        while self.peek_punct() == Some(".") {
+            self.pos += 1;
+            path.push(self.ident()?);
+        }
+        let text = format!("{}->{}", path[0], path[1..].join("."));
+
+        // The longest path naming a register wins, what follows is a field
+        for len in (2..=path.len()).rev() {
+            let register = match self.index.register(&path[..len].join(".")) {
+                Some(r) => r,
+                None => continue,
+            };
+            let address = register.address;
+            let size = register.properties.size.unwrap_or(32);
+            return match &path[len..] {
+                [] => Ok(Expr::Register { address, size }),
+                [field] => register
+                    .info
+                    .fields
+                    .iter()
+                    .flatten()
+                    .map(|f| f.expand())
+                    .collect::<Result<Vec<_>>>()?
+                    .into_iter()
+                    .flatten()
+                    .find(|f| f.name.eq_ignore_ascii_case(field))
+                    .map(|f| Expr::Field {
+                        address,
+                        size,
+                        offset: f.bit_range.offset,
+                        width: f.bit_range.width,
+                    })
+                    .ok_or_else(|| ConditionError::UnknownField(text).into()),
+                _ => Err(ConditionError::UnknownField(text).into()),
+            };
+        }
+        Err(ConditionError::UnknownRegister(text).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_evaluate() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <peripherals>
+                    <peripheral>
+                        <name>SYSCFG</name>
+                        <baseAddress>0x40010000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>CFGR</name>
+                                <addressOffset>0x0</addressOffset>
+                                <fields>
+                                    <field>
+                                        <name>EN</name>
+                                        <bitRange>[0:0]</bitRange>
+                                    </field>
+                                    <field>
+                                        <name>MODE</name>
+                                        <bitRange>[5:4]</bitRange>
+                                    </field>
+                                </fields>
+                            </register>
+                            <cluster>
+                                <name>PWR</name>
+                                <addressOffset>0x10</addressOffset>
+                                <register>
+                                    <name>CR</name>
+                                    <addressOffset>0x4</addressOffset>
+                                    <size>16</size>
+                                </register>
+                            </cluster>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+        let index = DeviceIndex::new(&device).unwrap();
+
+        let mut reads = Vec::new();
+        let mut values = |address: u64, size: u32| -> Result<u64> {
+            reads.push((address, size));
+            match address {
+                0x4001_0000 => Ok(0x21),
+                0x4001_0014 => Ok(0),
+                _ => anyhow::bail!("unmapped address {:#x}", address),
+            }
+        };
+
+        let condition = Condition::parse("(SYSCFG->CFGR & 0x1) == 0", &index).unwrap();
+        assert!(!condition.evaluate(&mut values).unwrap());
+
+        let condition =
+            Condition::parse("SYSCFG->CFGR.MODE != 2UL || !SYSCFG->PWR.CR", &index).unwrap();
+        assert!(condition.evaluate(&mut values).unwrap());
+
+        let condition = Condition::parse("~SYSCFG->CFGR.EN & 1 && 1 << 4 > 8", &index).unwrap();
+        assert!(!condition.evaluate(&mut values).unwrap());
+
+        assert_eq!(
+            reads,
+            [
+                (0x4001_0000, 32),
+                (0x4001_0000, 32),
+                (0x4001_0014, 16),
+                (0x4001_0000, 32)
+            ]
+        );
+
+        let error = |text: &str| {
+            Condition::parse(text, &index)
+                .unwrap_err()
+                .downcast::<ConditionError>()
+                .unwrap()
+        };
+        assert_eq!(
+            error("SYSCFG->CR == 0"),
+            ConditionError::UnknownRegister(String::from("SYSCFG->CR"))
+        );
+        assert_eq!(
+            error("SYSCFG->CFGR.LOCK"),
+            ConditionError::UnknownField(String::from("SYSCFG->CFGR.LOCK"))
+        );
+        assert_eq!(
+            error("(SYSCFG->CFGR"),
+            ConditionError::Syntax(String::from("(SYSCFG->CFGR"), String::from("expected `)`"))
+        );
+
+        let nested = format!("{}1{}", "(".repeat(100_000), ")".repeat(100_000));
+        let chained = vec!["1"; 100_000].join(" | ");
+        for text in [nested, "!".repeat(100_000) + "1", chained] {
+            assert_eq!(
+                error(&text),
+                ConditionError::Syntax(text.clone(), String::from("expression nested too deeply"))
+            );
+        }
+        let nested = format!("{}1{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert!(Condition::parse(&nested, &index).is_ok());
+    }
+}
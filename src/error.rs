@@ -21,6 +21,12 @@ pub enum SVDError {
     NameMismatch(Element),
     #[error("unknown access variant '{1}' found")]
     UnknownAccessType(Element, String),
+    #[error("unknown protection variant '{1}' found")]
+    UnknownProtection(Element, String),
+    #[error("unknown SAU region access variant '{1}' found")]
+    UnknownSauAccess(Element, String),
+    #[error("unknown address block usage variant '{1}' found")]
+    UnknownAddressBlockUsage(Element, String),
     #[error("Bit range invalid, {1:?}")]
     InvalidBitRange(Element, InvalidBitRange),
     #[error("Unknown write constraint")]
@@ -35,6 +41,16 @@ pub enum SVDError {
     InvalidRegisterCluster(Element, String),
     #[error("Invalid modifiedWriteValues variant, found {1}")]
     InvalidModifiedWriteValues(Element, String),
+    #[error("Invalid dataType variant, found {1}")]
+    InvalidDataType(Element, String),
+    #[error("Invalid readAction variant, found {1}")]
+    InvalidReadAction(Element, String),
+    #[error("Array `{1}` has no `%s` placeholder in its name")]
+    MissingDimPlaceholder(Element, String),
+    #[error("Array `{1}` has {2} elements but {3} indices")]
+    DimIndexMismatch(Element, String, u32, usize),
+    #[error("Array of {1} elements {2:#x} apart from offset {0:#x} runs past 32 bits")]
+    DimOverflow(u32, u32, u32),
     #[error("The content of the element could not be parsed to a boolean value {1}: {2}")]
     InvalidBooleanValue(Element, String, core::str::ParseBoolError),
     #[error("encoding method not implemented for svd object {0}")]
@@ -50,3 +66,23 @@ pub enum InvalidBitRange {
     ParseError,
     MsbLsb,
 }
+
+/// Errors raised while resolving `derivedFrom` references
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum DeriveError {
+    #[error("`{0}` is derived from `{1}`, which was not found")]
+    NotFound(String, String),
+    #[error("Cyclic derivation: {}", .0.join(" -> "))]
+    Cyclic(Vec<String>),
+}
+
+/// Errors raised while parsing a `disableCondition`
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ConditionError {
+    #[error("Invalid disable condition `{0}`: {1}")]
+    Syntax(String, String),
+    #[error("Unknown register `{0}` in disable condition")]
+    UnknownRegister(String),
+    #[error("Unknown field `{0}` in disable condition")]
+    UnknownField(String),
+}
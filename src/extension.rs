@@ -0,0 +1,263 @@
+//! Vendor extensions.
+//! Typed parsing of the `<vendorExtensions>` subtree of a device
+//!
+//! A vendor extension implements `VendorExtension` and is registered in an
+//! `ExtensionParsers` given to `parse_with`. The children of `<vendorExtensions>` it
+//! handles are parsed into it and stored in `Device::extensions`, the other ones stay
+//! in `Device::vendor_extensions`.
+
+use core::any::Any;
+use core::fmt;
+use xmltree::Element;
+
+#[cfg(feature = "unproven")]
+use crate::encode::Encode;
+use crate::error::*;
+use crate::types::Parse;
+
+/// Encoding requirement of vendor extensions, only needed when encoding is enabled
+#[cfg(feature = "unproven")]
+pub trait EncodeExtension: Encode<Error = anyhow::Error> {}
+#[cfg(feature = "unproven")]
+impl<T: Encode<Error = anyhow::Error>> EncodeExtension for T {}
+
+/// Encoding requirement of vendor extensions, only needed when encoding is enabled
+#[cfg(not(feature = "unproven"))]
+pub trait EncodeExtension {}
+#[cfg(not(feature = "unproven"))]
+impl<T> EncodeExtension for T {}
+
+/// A typed child of `<vendorExtensions>`
+pub trait VendorExtension:
+    Parse<Object = Self, Error = anyhow::Error>
+    + EncodeExtension
+    + Clone
+    + PartialEq
+    + fmt::Debug
+    + Send
+    + Sync
+    + 'static
+{
+    /// Namespace URI of the elements handled by this extension
+    const NAMESPACE: &'static str;
+
+    /// Whether a child of `<vendorExtensions>` is handled by this extension
+    ///
+    /// Matches the elements of `NAMESPACE` by default.
+    fn handles(tree: &Element) -> bool {
+        tree.namespace.as_deref() == Some(Self::NAMESPACE)
+    }
+}
+
+/// Object safe view of a `VendorExtension`
+trait DynExtension: fmt::Debug + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn clone_box(&self) -> Box<dyn DynExtension>;
+    fn eq_dyn(&self, other: &dyn DynExtension) -> bool;
+    #[cfg(feature = "unproven")]
+    fn encode_dyn(&self) -> Result<Element>;
+}
+
+impl<T: VendorExtension> DynExtension for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn clone_box(&self) -> Box<dyn DynExtension> {
+        Box::new(self.clone())
+    }
+
+    fn eq_dyn(&self, other: &dyn DynExtension) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+
+    #[cfg(feature = "unproven")]
+    fn encode_dyn(&self) -> Result<Element> {
+        self.encode()
+    }
+}
+
+impl Clone for Box<dyn DynExtension> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+/// Typed vendor extensions of a device, retrieved by type
+#[derive(Clone, Debug, Default)]
+pub struct Extensions {
+    items: Vec<Box<dyn DynExtension>>,
+}
+
+impl Extensions {
+    /// Returns the first extension of type `T`
+    pub fn get<T: VendorExtension>(&self) -> Option<&T> {
+        self.iter::<T>().next()
+    }
+
+    /// Iterates over the extensions of type `T`, in document order
+    pub fn iter<T: VendorExtension>(&self) -> impl Iterator<Item = &T> {
+        self.items
+            .iter()
+            .filter_map(|item| item.as_any().downcast_ref::<T>())
+    }
+
+    /// Adds an extension, encoded after the existing ones
+    pub fn insert<T: VendorExtension>(&mut self, extension: T) {
+        self.items.push(Box::new(extension));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Encodes every extension, in order
+    #[cfg(feature = "unproven")]
+    pub(crate) fn encode(&self) -> Result<Vec<Element>> {
+        self.items.iter().map(|item| item.encode_dyn()).collect()
+    }
+}
+
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Self) -> bool {
+        self.items.len() == other.items.len()
+            && self
+                .items
+                .iter()
+                .zip(&other.items)
+                .all(|(a, b)| a.eq_dyn(b.as_ref()))
+    }
+}
+
+struct ExtensionParser {
+    handles: fn(&Element) -> bool,
+    parse: fn(&Element) -> Result<Box<dyn DynExtension>>,
+}
+
+fn parse_boxed<T: VendorExtension>(tree: &Element) -> Result<Box<dyn DynExtension>> {
+    Ok(Box::new(T::parse(tree)?))
+}
+
+/// Registry of the vendor extensions known to `parse_with`
+#[derive(Default)]
+pub struct ExtensionParsers {
+    parsers: Vec<ExtensionParser>,
+}
+
+impl ExtensionParsers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers an extension
+    ///
+    /// A subtree handled by several extensions goes to the first registered one.
+    pub fn register<T: VendorExtension>(&mut self) -> &mut Self {
+        self.parsers.push(ExtensionParser {
+            handles: T::handles,
+            parse: parse_boxed::<T>,
+        });
+        self
+    }
+
+    /// Parses the handled children of a `<vendorExtensions>` element and removes them from it
+    pub(crate) fn parse(&self, vendor_extensions: &mut Element) -> Result<Extensions> {
+        let mut extensions = Extensions::default();
+        let mut unhandled = Vec::new();
+        for child in vendor_extensions.children.drain(..) {
+            match self.parsers.iter().find(|p| (p.handles)(&child)) {
+                Some(parser) => extensions.items.push(
+                    (parser.parse)(&child)
+                        .with_context(|| format!("In vendor extension `{}`", child.name))?,
+                ),
+                None => unhandled.push(child),
+            }
+        }
+        vendor_extensions.children = unhandled;
+        Ok(extensions)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unproven")]
+mod tests {
+    use super::*;
+    use crate::elementext::ElementExt;
+    use crate::new_element;
+
+    const NAMESPACE: &str = "http://example.com/clocks";
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct ClockTree {
+        sources: Vec<String>,
+    }
+
+    impl Parse for ClockTree {
+        type Object = ClockTree;
+        type Error = anyhow::Error;
+
+        fn parse(tree: &Element) -> Result<ClockTree> {
+            let sources: Result<Vec<_>> = tree.children.iter().map(|c| c.get_text()).collect();
+            Ok(ClockTree { sources: sources? })
+        }
+    }
+
+    impl Encode for ClockTree {
+        type Error = anyhow::Error;
+
+        fn encode(&self) -> Result<Element> {
+            let mut elem = new_element("clocks", None);
+            elem.prefix = Some(String::from("clk"));
+            elem.namespace = Some(String::from(NAMESPACE));
+            for s in &self.sources {
+                elem.children.push(new_element("source", Some(s.clone())));
+            }
+            Ok(elem)
+        }
+    }
+
+    impl VendorExtension for ClockTree {
+        const NAMESPACE: &'static str = NAMESPACE;
+    }
+
+    #[test]
+    fn parse_with() {
+        let xml = "
+            <device xmlns:clk=\"http://example.com/clocks\">
+                <name>DEV</name>
+                <peripherals>
+                </peripherals>
+                <vendorExtensions>
+                    <clk:clocks>
+                        <clk:source>HSI</clk:source>
+                        <clk:source>PLL</clk:source>
+                    </clk:clocks>
+                    <errata id=\"108\">RAM retention</errata>
+                </vendorExtensions>
+            </device>
+        ";
+        let mut parsers = ExtensionParsers::new();
+        parsers.register::<ClockTree>();
+
+        let device = crate::parse_with(xml, &parsers).unwrap();
+        let clocks = device.extensions.get::<ClockTree>().unwrap();
+        assert_eq!(clocks.sources, ["HSI", "PLL"]);
+        let raw = device.vendor_extensions.as_ref().unwrap();
+        assert_eq!(raw.children.len(), 1);
+        assert_eq!(raw.children[0].name, "errata");
+
+        let encoded = crate::encode(&device).unwrap();
+        let reparsed = crate::parse_with(&encoded, &parsers).unwrap();
+        assert_eq!(reparsed.extensions, device.extensions);
+        assert_eq!(reparsed.vendor_extensions.unwrap().children.len(), 1);
+
+        // Without the parser the extension stays raw XML
+        let device = crate::parse(xml).unwrap();
+        assert!(device.extensions.is_empty());
+        assert_eq!(device.vendor_extensions.unwrap().children.len(), 2);
+    }
+}
@@ -0,0 +1,316 @@
+//! Device index.
+//! Looks up peripherals, registers and fields by address, name, group or interrupt
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::error::*;
+use crate::iter::RegisterItem;
+use crate::svd::{
+    device::Device, fieldinfo::FieldInfo, interrupt::Interrupt, peripheral::Peripheral,
+};
+
+/// Lookup tables prebuilt from a `Device`
+///
+/// Peripherals that are `derivedFrom` another one only get the registers they declare
+/// themselves, run `derive_from::resolve` first to index inherited ones.
+pub struct DeviceIndex<'a> {
+    device: &'a Device,
+    /// Number of bits of one address unit
+    unit_bits: u32,
+    /// Registers sorted by address
+    registers: Vec<RegisterItem>,
+    /// Length in address units of the longest register
+    max_register_len: u64,
+    /// Address ranges of the peripherals, sorted by start
+    ranges: Vec<(Range<u64>, usize)>,
+    peripheral_names: HashMap<String, usize>,
+    register_names: HashMap<String, usize>,
+    groups: HashMap<String, Vec<usize>>,
+    interrupts: HashMap<u32, Vec<(usize, usize)>>,
+}
+
+impl<'a> DeviceIndex<'a> {
+    /// Indexes a device
+    ///
+    /// Fails on arrays whose instances do not fit in 32-bit addresses or offsets.
+    pub fn new(device: &'a Device) -> Result<DeviceIndex<'a>> {
+        let unit_bits = device.address_unit_bits.unwrap_or(8).max(1);
+
+        let mut registers = device.registers().collect::<Result<Vec<_>>>()?;
+        registers.sort_by_key(|r| r.address);
+        let max_register_len = registers
+            .iter()
+            .map(|r| r.span(unit_bits).end - r.address)
+            .max()
+            .unwrap_or(0);
+
+        let mut ranges = Vec::new();
+        let mut peripheral_names = HashMap::new();
+        let mut groups = HashMap::new();
+        let mut interrupts = HashMap::new();
+        for (i, p) in device.peripherals.iter().enumerate() {
+            for (name, _) in p.instances()? {
+                if p.address_block.is_empty() {
+                    let prefix = [name.clone()];
+                    let span = registers
+                        .iter()
+                        .filter(|r| r.path.starts_with(&prefix))
+                        .map(|r| r.span(unit_bits))
+                        .fold(None, |span: Option<Range<u64>>, r| match span {
+                            None => Some(r),
+                            Some(s) => Some(s.start.min(r.start)..s.end.max(r.end)),
+                        });
+                    if let Some(span) = span {
+                        ranges.push((span, i));
+                    }
+                }
+                peripheral_names.entry(name.to_lowercase()).or_insert(i);
+            }
+            ranges.extend(p.address_ranges()?.into_iter().map(|(_, r)| (r, i)));
+            if let Some(group) = &p.group_name {
+                groups
+                    .entry(group.to_lowercase())
+                    .or_insert_with(Vec::new)
+                    .push(i);
+            }
+            for (j, interrupt) in p.interrupt.iter().enumerate() {
+                interrupts
+                    .entry(interrupt.value)
+                    .or_insert_with(Vec::new)
+                    .push((i, j));
+            }
+        }
+        ranges.sort_by_key(|(r, _)| r.start);
+
+        let mut register_names = HashMap::new();
+        for (i, r) in registers.iter().enumerate() {
+            register_names.entry(r.name().to_lowercase()).or_insert(i);
+        }
+
+        Ok(DeviceIndex {
+            device,
+            unit_bits,
+            registers,
+            max_register_len,
+            ranges,
+            peripheral_names,
+            register_names,
+            groups,
+            interrupts,
+        })
+    }
+
+    /// The indexed device
+    pub fn device(&self) -> &'a Device {
+        self.device
+    }
+
+    /// Peripheral with an address block containing `address`
+    ///
+    /// Reserved blocks do not count. Peripherals without address blocks span their
+    /// registers. Each instance of a peripheral array has its own range, the array as a
+    /// whole is returned.
+    pub fn peripheral_at(&self, address: u64) -> Option<&'a Peripheral> {
+        self.ranges
+            .iter()
+            .take_while(|(r, _)| r.start <= address)
+            .find(|(r, _)| r.contains(&address))
+            .map(|(_, i)| &self.device.peripherals[*i])
+    }
+
+    /// Registers containing `address`, including alternate ones
+    pub fn registers_at(&self, address: u64) -> Vec<&RegisterItem> {
+        self.registers_in(address..address.saturating_add(1))
+    }
+
+    /// First register containing `address`
+    pub fn register_at(&self, address: u64) -> Option<&RegisterItem> {
+        self.registers_at(address).into_iter().next()
+    }
+
+    /// Registers overlapping an address range
+    pub fn registers_in(&self, range: Range<u64>) -> Vec<&RegisterItem> {
+        let first = self
+            .registers
+            .partition_point(|r| r.address + self.max_register_len <= range.start);
+        self.registers[first..]
+            .iter()
+            .take_while(|r| r.address < range.end)
+            .filter(|r| r.span(self.unit_bits).end > range.start)
+            .collect()
+    }
+
+    /// Fields of the register at `address` covering `bit`
+    ///
+    /// `bit` counts from the least significant bit of the address unit at `address`, which
+    /// may lie in the middle of the register. Fails on field arrays whose bit offsets do not
+    /// fit in 32 bits.
+    pub fn fields_at(&self, address: u64, bit: u32) -> Result<Vec<FieldInfo>> {
+        let register = match self.register_at(address) {
+            Some(r) => r,
+            None => return Ok(Vec::new()),
+        };
+        let bit = (address - register.address) * u64::from(self.unit_bits) + u64::from(bit);
+        let mut fields = Vec::new();
+        for f in register.info.fields.iter().flatten() {
+            fields.extend(f.expand()?);
+        }
+        Ok(fields
+            .into_iter()
+            .filter(|f| {
+                let range = &f.bit_range;
+                u64::from(range.lsb()) <= bit
+                    && bit < u64::from(range.offset) + u64::from(range.width)
+            })
+            .collect())
+    }
+
+    /// Peripheral by name, ignoring case
+    ///
+    /// Instances of peripheral arrays are found by their own name, such as `UART1`.
+    pub fn peripheral(&self, name: &str) -> Option<&'a Peripheral> {
+        self.peripheral_names
+            .get(&name.to_lowercase())
+            .map(|i| &self.device.peripherals[*i])
+    }
+
+    /// Register by dotted path such as `PERIPH.CLUSTER.REG`, ignoring case
+    ///
+    /// Array instances are named with their index, e.g. `DMA.CH1.CR`.
+    pub fn register(&self, path: &str) -> Option<&RegisterItem> {
+        self.register_names
+            .get(&path.to_lowercase())
+            .map(|i| &self.registers[*i])
+    }
+
+    /// Peripherals of a group, ignoring case
+    pub fn group(&self, group_name: &str) -> Vec<&'a Peripheral> {
+        self.groups
+            .get(&group_name.to_lowercase())
+            .map(|ps| ps.iter().map(|i| &self.device.peripherals[*i]).collect())
+            .unwrap_or_default()
+    }
+
+    /// Interrupts with the given number, along with their peripheral
+    pub fn interrupt(&self, value: u32) -> Vec<(&'a Peripheral, &'a Interrupt)> {
+        self.interrupts
+            .get(&value)
+            .map(|is| {
+                is.iter()
+                    .map(|&(i, j)| {
+                        let p = &self.device.peripherals[i];
+                        (p, &p.interrupt[j])
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookup() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <size>32</size>
+                <peripherals>
+                    <peripheral>
+                        <name>RCC</name>
+                        <groupName>RCC</groupName>
+                        <baseAddress>0x40021000</baseAddress>
+                        <addressBlock>
+                            <offset>0</offset>
+                            <size>0x400</size>
+                            <usage>registers</usage>
+                        </addressBlock>
+                        <addressBlock>
+                            <offset>0x400</offset>
+                            <size>0x400</size>
+                            <usage>reserved</usage>
+                        </addressBlock>
+                        <interrupt>
+                            <name>RCC</name>
+                            <value>5</value>
+                        </interrupt>
+                        <registers>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                            <register>
+                                <name>APB%sENR</name>
+                                <addressOffset>0x14</addressOffset>
+                                <dim>2</dim>
+                                <dimIncrement>4</dimIncrement>
+                                <fields>
+                                    <field>
+                                        <name>DMAEN</name>
+                                        <bitOffset>0</bitOffset>
+                                        <bitWidth>1</bitWidth>
+                                    </field>
+                                    <field>
+                                        <name>TIMEN</name>
+                                        <bitRange>[11:8]</bitRange>
+                                    </field>
+                                    <field>
+                                        <name>LAST</name>
+                                        <bitOffset>0xffffffff</bitOffset>
+                                        <bitWidth>2</bitWidth>
+                                    </field>
+                                </fields>
+                            </register>
+                        </registers>
+                    </peripheral>
+                    <peripheral>
+                        <name>GPIOA</name>
+                        <groupName>GPIO</groupName>
+                        <baseAddress>0x48000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>MODER</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                            <register>
+                                <name>ODR</name>
+                                <addressOffset>0x14</addressOffset>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+        let index = DeviceIndex::new(&device).unwrap();
+
+        assert_eq!(index.peripheral_at(0x4002_13fc).unwrap().name, "RCC");
+        assert!(index.peripheral_at(0x4002_1400).is_none());
+        assert_eq!(index.peripheral_at(0x4800_0017).unwrap().name, "GPIOA");
+        assert!(index.peripheral_at(0x4800_0018).is_none());
+
+        let register = index.register_at(0x4002_101a).unwrap();
+        assert_eq!(register.name(), "RCC.APB1ENR");
+        assert_eq!(register.index, Some(1));
+        assert!(index.register_at(0x4002_1004).is_none());
+        assert!(index.registers_at(u64::MAX).is_empty());
+
+        let fields = index.fields_at(0x4002_1019, 2).unwrap();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].name, "TIMEN");
+        let fields = index.fields_at(0x4002_1018, u32::MAX).unwrap();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].name, "LAST");
+
+        assert_eq!(index.peripheral("gpioa").unwrap().name, "GPIOA");
+        assert_eq!(index.register("rcc.apb0enr").unwrap().address, 0x4002_1014);
+        assert_eq!(index.group("gpio").len(), 1);
+        assert_eq!(index.interrupt(5)[0].1.name, "RCC");
+        assert!(index.interrupt(6).is_empty());
+    }
+}
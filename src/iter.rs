@@ -0,0 +1,330 @@
+//! Register iteration.
+//! Walks the registers of peripherals through nested clusters and arrays
+
+use std::ops::Range;
+
+use crate::error::*;
+
+use crate::svd::{
+    peripheral::Peripheral, register::Register, registercluster::RegisterCluster,
+    registerinfo::RegisterInfo, registerproperties::RegisterProperties,
+};
+
+/// A concrete register along with its location in the device
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegisterItem {
+    /// Names of the peripheral, the enclosing clusters and the register,
+    /// with array indices substituted
+    pub path: Vec<String>,
+    /// Absolute address: peripheral base address plus cluster and register offsets
+    pub address: u64,
+    /// Position of the register in its array, `None` for single registers
+    pub index: Option<u32>,
+    /// Properties cascaded down to the register
+    pub properties: RegisterProperties,
+    /// The register itself, expanded when part of an array
+    pub info: RegisterInfo,
+}
+
+impl RegisterItem {
+    /// Dotted path of the register, such as `PERIPH.CLUSTER.REG`
+    pub fn name(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// Addresses occupied by the register, assuming 32 bits when its size is unknown
+    pub fn span(&self, address_unit_bits: u32) -> Range<u64> {
+        let size = u64::from(self.properties.size.unwrap_or(32));
+        let len = size.div_ceil(u64::from(address_unit_bits.max(1))).max(1);
+        self.address..self.address + len
+    }
+}
+
+/// Iterator over the registers of one or more peripherals, see `Device::registers`
+///
+/// Arrays whose instances do not fit in 32-bit addresses or offsets are yielded as errors,
+/// iteration then goes on after them.
+pub struct RegisterIter<'a> {
+    peripherals: std::vec::IntoIter<(&'a Peripheral, RegisterProperties)>,
+    stack: Vec<Frame<'a>>,
+    pending: std::vec::IntoIter<RegisterItem>,
+}
+
+/// Registers and clusters left to visit in a peripheral or cluster instance
+struct Frame<'a> {
+    children: std::slice::Iter<'a, RegisterCluster>,
+    path: Vec<String>,
+    address: u64,
+    properties: RegisterProperties,
+}
+
+impl<'a> RegisterIter<'a> {
+    /// Iterates over `peripherals`, each one given with the properties it inherits
+    pub(crate) fn new<I>(peripherals: I) -> Self
+    where
+        I: IntoIterator<Item = (&'a Peripheral, RegisterProperties)>,
+    {
+        RegisterIter {
+            peripherals: peripherals.into_iter().collect::<Vec<_>>().into_iter(),
+            stack: Vec::new(),
+            pending: Vec::new().into_iter(),
+        }
+    }
+
+    /// Pushes a frame for every instance of a peripheral
+    fn push_peripheral(&mut self, p: &'a Peripheral, properties: RegisterProperties) -> Result<()> {
+        let properties = p.default_register_properties.inherit(&properties);
+        let instances = p
+            .instances()
+            .with_context(|| format!("In peripheral `{}`", p.name))?;
+        self.stack.extend(
+            instances
+                .into_iter()
+                .rev()
+                .map(|(name, base_address)| Frame {
+                    children: p.registers.as_deref().unwrap_or(&[]).iter(),
+                    path: vec![name],
+                    address: u64::from(base_address),
+                    properties,
+                }),
+        );
+        Ok(())
+    }
+}
+
+impl<'a> Iterator for RegisterIter<'a> {
+    type Item = Result<RegisterItem>;
+
+    fn next(&mut self) -> Option<Result<RegisterItem>> {
+        loop {
+            if let Some(item) = self.pending.next() {
+                return Some(Ok(item));
+            }
+            let frame = match self.stack.last_mut() {
+                Some(frame) => frame,
+                None => {
+                    let (p, properties) = self.peripherals.next()?;
+                    if let Err(e) = self.push_peripheral(p, properties) {
+                        return Some(Err(e));
+                    }
+                    continue;
+                }
+            };
+            match frame.children.next() {
+                None => {
+                    self.stack.pop();
+                }
+                Some(RegisterCluster::Register(r)) => {
+                    let properties = r.properties().inherit(&frame.properties);
+                    let is_array = match r {
+                        Register::Single(_) => false,
+                        Register::Array(..) => true,
+                    };
+                    let infos = match r.expand() {
+                        Ok(infos) => infos,
+                        Err(e) => {
+                            let path = format!("{}.{}", frame.path.join("."), r.name);
+                            return Some(Err(e.context(format!("In register `{}`", path))));
+                        }
+                    };
+                    let items: Vec<_> = infos
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, info)| {
+                            let mut path = frame.path.clone();
+                            path.push(info.name.clone());
+                            RegisterItem {
+                                path,
+                                address: frame.address + u64::from(info.address_offset),
+                                index: if is_array { Some(i as u32) } else { None },
+                                properties,
+                                info,
+                            }
+                        })
+                        .collect();
+                    self.pending = items.into_iter();
+                }
+                Some(RegisterCluster::Cluster(c)) => {
+                    let properties = c.default_register_properties.inherit(&frame.properties);
+                    let instances = match c.expand() {
+                        Ok(instances) => instances,
+                        Err(e) => {
+                            let path = format!("{}.{}", frame.path.join("."), c.name);
+                            return Some(Err(e.context(format!("In cluster `{}`", path))));
+                        }
+                    };
+                    let frames: Vec<_> = instances
+                        .into_iter()
+                        .rev()
+                        .map(|info| {
+                            let mut path = frame.path.clone();
+                            path.push(info.name);
+                            Frame {
+                                children: c.children.iter(),
+                                path,
+                                address: frame.address + u64::from(info.address_offset),
+                                properties,
+                            }
+                        })
+                        .collect();
+                    self.stack.extend(frames);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[test]
+    fn registers() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <size>32</size>
+                <peripherals>
+                    <peripheral>
+                        <name>DMA</name>
+                        <baseAddress>0x40020000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>ISR</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                            <cluster>
+                                <name>CH%s</name>
+                                <addressOffset>0x8</addressOffset>
+                                <dim>2</dim>
+                                <dimIncrement>0x14</dimIncrement>
+                                <size>16</size>
+                                <register>
+                                    <name>CR</name>
+                                    <addressOffset>0x0</addressOffset>
+                                </register>
+                                <register>
+                                    <name>PAR%s</name>
+                                    <addressOffset>0x8</addressOffset>
+                                    <dim>2</dim>
+                                    <dimIncrement>4</dimIncrement>
+                                </register>
+                            </cluster>
+                            <register>
+                                <name>IFCR</name>
+                                <addressOffset>0x4</addressOffset>
+                            </register>
+                        </registers>
+                    </peripheral>
+                    <peripheral>
+                        <name>GPIO</name>
+                        <baseAddress>0x48000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>MODER</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                        </registers>
+                    </peripheral>
+                    <peripheral>
+                        <name>UART%s</name>
+                        <baseAddress>0x40004000</baseAddress>
+                        <dim>2</dim>
+                        <dimIncrement>0x400</dimIncrement>
+                        <dimIndex>A,B</dimIndex>
+                        <registers>
+                            <register>
+                                <name>DR</name>
+                                <addressOffset>0x4</addressOffset>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let registers: Vec<_> = device
+            .registers()
+            .map(Result::unwrap)
+            .map(|r| (r.name(), r.address, r.index, r.properties.size))
+            .collect();
+        assert_eq!(
+            registers,
+            [
+                ("DMA.ISR".to_string(), 0x4002_0000, None, Some(32)),
+                ("DMA.CH0.CR".to_string(), 0x4002_0008, None, Some(16)),
+                ("DMA.CH0.PAR0".to_string(), 0x4002_0010, Some(0), Some(16)),
+                ("DMA.CH0.PAR1".to_string(), 0x4002_0014, Some(1), Some(16)),
+                ("DMA.CH1.CR".to_string(), 0x4002_001c, None, Some(16)),
+                ("DMA.CH1.PAR0".to_string(), 0x4002_0024, Some(0), Some(16)),
+                ("DMA.CH1.PAR1".to_string(), 0x4002_0028, Some(1), Some(16)),
+                ("DMA.IFCR".to_string(), 0x4002_0004, None, Some(32)),
+                ("GPIO.MODER".to_string(), 0x4800_0000, None, Some(32)),
+                ("UARTA.DR".to_string(), 0x4000_4004, None, Some(32)),
+                ("UARTB.DR".to_string(), 0x4000_4404, None, Some(32)),
+            ]
+        );
+
+        let registers: Vec<_> = device.peripherals[1]
+            .registers()
+            .map(Result::unwrap)
+            .map(|r| (r.name(), r.properties.size))
+            .collect();
+        assert_eq!(registers, [("GPIO.MODER".to_string(), None)]);
+    }
+
+    #[test]
+    fn array_overflow() {
+        use crate::svd::{register::Register, registercluster::RegisterCluster};
+
+        let mut device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <peripherals>
+                    <peripheral>
+                        <name>P</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>BUF%s</name>
+                                <addressOffset>0x0</addressOffset>
+                                <dim>3</dim>
+                                <dimIncrement>4</dimIncrement>
+                            </register>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x10</addressOffset>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+        match &mut device.peripherals[0].registers.as_mut().unwrap()[0] {
+            RegisterCluster::Register(Register::Array(_, array_info)) => {
+                array_info.dim_increment = 0x8000_0000
+            }
+            _ => panic!(),
+        }
+
+        let registers: Vec<_> = device
+            .registers()
+            .map(|r| r.map(|r| r.name()).map_err(|e| format!("{:#}", e)))
+            .collect();
+        assert_eq!(
+            registers,
+            [
+                Err(String::from(
+                    "In register `P.BUF%s`: Array of 3 elements 0x80000000 apart from offset 0x0 \
+                     runs past 32 bits"
+                )),
+                Ok(String::from("P.CR")),
+            ]
+        );
+        assert!(crate::index::DeviceIndex::new(&device).is_err());
+    }
+}
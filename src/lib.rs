@@ -48,6 +48,18 @@ use encode::Encode;
 // Types defines simple types and parse/encode implementations
 pub mod types;
 
+// Iter walks registers through clusters and arrays
+pub mod iter;
+// Index looks up device elements by address or name
+pub mod index;
+// Validate checks the consistency of a device
+pub mod validate;
+// Disable condition evaluates the `disableCondition` of peripherals
+pub mod disable_condition;
+// Extension parses vendor extensions into user types
+pub mod extension;
+use extension::ExtensionParsers;
+
 #[cfg(feature = "derive-from")]
 pub mod derive_from;
 #[cfg(feature = "derive-from")]
@@ -60,6 +72,17 @@ pub fn parse(xml: &str) -> Result<Device> {
     Device::parse(&tree)
 }
 
+/// Parses the contents of an SVD (XML) string, handing vendor extensions to `parsers`
+///
+/// See the `extension` module.
+pub fn parse_with(xml: &str, parsers: &ExtensionParsers) -> Result<Device> {
+    let mut device = parse(xml)?;
+    if let Some(vendor_extensions) = &mut device.vendor_extensions {
+        device.extensions = parsers.parse(vendor_extensions)?;
+    }
+    Ok(device)
+}
+
 /// Encodes a device object to an SVD (XML) string
 #[cfg(feature = "unproven")]
 pub fn encode(d: &Device) -> Result<String> {
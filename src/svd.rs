@@ -7,12 +7,18 @@ pub use self::endian::Endian;
 pub mod cpu;
 pub use self::cpu::Cpu;
 
+pub mod cpuname;
+pub use self::cpuname::CpuName;
+
 pub mod interrupt;
 pub use self::interrupt::Interrupt;
 
 pub mod access;
 pub use self::access::Access;
 
+pub mod protection;
+pub use self::protection::Protection;
+
 pub mod bitrange;
 pub use self::bitrange::BitRange;
 
@@ -22,6 +28,9 @@ pub use self::writeconstraint::WriteConstraint;
 pub mod usage;
 pub use self::usage::Usage;
 
+pub mod bitpattern;
+pub use self::bitpattern::BitPattern;
+
 pub mod enumeratedvalue;
 pub use self::enumeratedvalue::EnumeratedValue;
 
@@ -58,11 +67,23 @@ pub use self::registercluster::RegisterCluster;
 pub mod dimelement;
 pub use self::dimelement::DimElement;
 
+pub mod dimarrayindex;
+pub use self::dimarrayindex::DimArrayIndex;
+
 pub mod peripheral;
 pub use self::peripheral::Peripheral;
 
+pub mod peripheralinfo;
+pub use self::peripheralinfo::PeripheralInfo;
+
 pub mod device;
 pub use self::device::Device;
 
 pub mod modifiedwritevalues;
 pub use self::modifiedwritevalues::ModifiedWriteValues;
+
+pub mod datatype;
+pub use self::datatype::DataType;
+
+pub mod readaction;
+pub use self::readaction::ReadAction;
@@ -11,13 +11,24 @@ use crate::encode::Encode;
 use crate::error::*;
 #[cfg(feature = "unproven")]
 use crate::new_element;
+use crate::parse;
+use crate::svd::protection::Protection;
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
 pub struct AddressBlock {
     pub offset: u32,
     pub size: u32,
-    pub usage: String,
+    pub usage: AddressBlockUsage,
+    pub protection: Option<Protection>,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AddressBlockUsage {
+    Registers,
+    Buffer,
+    Reserved,
 }
 
 impl Parse for AddressBlock {
@@ -28,32 +39,69 @@ impl Parse for AddressBlock {
         Ok(AddressBlock {
             offset: tree.get_child_u32("offset")?,
             size: tree.get_child_u32("size")?,
-            usage: tree.get_child_text("usage")?,
+            usage: AddressBlockUsage::parse(tree.get_child_elem("usage")?)?,
+            protection: parse::optional::<Protection>("protection", tree)?,
         })
     }
 }
 
+impl Parse for AddressBlockUsage {
+    type Object = AddressBlockUsage;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<AddressBlockUsage> {
+        let text = tree.get_text()?;
+
+        match &text[..] {
+            "registers" => Ok(AddressBlockUsage::Registers),
+            "buffer" => Ok(AddressBlockUsage::Buffer),
+            "reserved" => Ok(AddressBlockUsage::Reserved),
+            _ => Err(SVDError::UnknownAddressBlockUsage(tree.clone(), text).into()),
+        }
+    }
+}
+
 #[cfg(feature = "unproven")]
 impl Encode for AddressBlock {
     type Error = anyhow::Error;
 
     fn encode(&self) -> Result<Element> {
+        let mut children = vec![
+            new_element("offset", Some(format!("{}", self.offset))),
+            new_element("size", Some(format!("0x{:08.x}", self.size))),
+            self.usage.encode()?,
+        ];
+        if let Some(v) = &self.protection {
+            children.push(v.encode()?);
+        }
+
         Ok(Element {
             prefix: None,
             namespace: None,
             namespaces: None,
             name: String::from("addressBlock"),
             attributes: HashMap::new(),
-            children: vec![
-                new_element("offset", Some(format!("{}", self.offset))),
-                new_element("size", Some(format!("0x{:08.x}", self.size))),
-                new_element("usage", Some(self.usage.clone())),
-            ],
+            children,
             text: None,
         })
     }
 }
 
+#[cfg(feature = "unproven")]
+impl Encode for AddressBlockUsage {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let text = match *self {
+            AddressBlockUsage::Registers => String::from("registers"),
+            AddressBlockUsage::Buffer => String::from("buffer"),
+            AddressBlockUsage::Reserved => String::from("reserved"),
+        };
+
+        Ok(new_element("usage", Some(text)))
+    }
+}
+
 #[cfg(test)]
 #[cfg(feature = "unproven")]
 mod tests {
@@ -62,18 +110,35 @@ mod tests {
 
     #[test]
     fn decode_encode() {
-        let tests = vec![(
-            AddressBlock {
-                offset: 0,
-                size: 0x00000800,
-                usage: String::from("registers"),
-            },
-            "<addressBlock>
+        let tests = vec![
+            (
+                AddressBlock {
+                    offset: 0,
+                    size: 0x00000800,
+                    usage: AddressBlockUsage::Registers,
+                    protection: None,
+                },
+                "<addressBlock>
                     <offset>0</offset>
                     <size>0x00000800</size>
                     <usage>registers</usage>
                 </addressBlock>",
-        )];
+            ),
+            (
+                AddressBlock {
+                    offset: 0x400,
+                    size: 0x00000200,
+                    usage: AddressBlockUsage::Buffer,
+                    protection: Some(Protection::Secure),
+                },
+                "<addressBlock>
+                    <offset>1024</offset>
+                    <size>0x00000200</size>
+                    <usage>buffer</usage>
+                    <protection>s</protection>
+                </addressBlock>",
+            ),
+        ];
 
         run_test::<AddressBlock>(&tests[..]);
     }
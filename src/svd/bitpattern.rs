@@ -0,0 +1,158 @@
+use core::fmt;
+use xmltree::Element;
+
+use crate::elementext::ElementExt;
+use crate::error::*;
+use crate::types::Parse;
+
+/// A value whose bits may be left unspecified, such as `#01x1`
+///
+/// Bits set in `mask` must match `value`, the other ones are don't-care. Bits above the
+/// written digits are zero and must match as well.
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BitPattern {
+    pub value: u32,
+    pub mask: u32,
+}
+
+impl BitPattern {
+    /// Whether a raw value matches the pattern
+    pub fn matches(&self, raw: u32) -> bool {
+        raw & self.mask == self.value
+    }
+
+    /// Whether some raw value matches both patterns
+    pub fn overlaps(&self, other: &BitPattern) -> bool {
+        (self.value ^ other.value) & self.mask & other.mask == 0
+    }
+
+    /// Whether every bit is specified
+    pub fn is_exact(&self) -> bool {
+        self.mask == u32::MAX
+    }
+
+    /// Number of raw values of `width` bits matching the pattern
+    pub fn count(&self, width: u32) -> u64 {
+        let bits = if width < 32 {
+            (1 << width) - 1
+        } else {
+            u32::MAX
+        };
+        if self.value & !bits != 0 {
+            return 0;
+        }
+        1 << (!self.mask & bits).count_ones()
+    }
+
+    /// Parses the digits of the binary form, `x` standing for a don't-care bit
+    fn parse_binary(digits: &str) -> Result<BitPattern> {
+        if digits.is_empty() || digits.len() > 32 {
+            anyhow::bail!("expected 1 to 32 binary digits");
+        }
+        let (mut value, mut mask) = (0u64, 0u64);
+        for c in digits.chars() {
+            value <<= 1;
+            mask <<= 1;
+            match c {
+                '0' => mask |= 1,
+                '1' => {
+                    value |= 1;
+                    mask |= 1;
+                }
+                'x' | 'X' => {}
+                c => anyhow::bail!("invalid binary digit `{}`", c),
+            }
+        }
+        // Bits above the written digits are zero
+        mask |= !0 << digits.len();
+        Ok(BitPattern {
+            value: value as u32,
+            mask: mask as u32,
+        })
+    }
+}
+
+impl From<u32> for BitPattern {
+    fn from(value: u32) -> Self {
+        BitPattern {
+            value,
+            mask: u32::MAX,
+        }
+    }
+}
+
+impl Parse for BitPattern {
+    type Object = BitPattern;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<BitPattern> {
+        let text = tree.get_text()?;
+
+        match text.strip_prefix('#').or_else(|| text.strip_prefix("0b")) {
+            Some(digits) => BitPattern::parse_binary(digits).context(format!("{} invalid", text)),
+            None => Ok(BitPattern::from(u32::parse(tree)?)),
+        }
+    }
+}
+
+/// Formats exact patterns in hexadecimal and the other ones in the `#01x1` form
+impl fmt::Display for BitPattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_exact() {
+            return write!(f, "0x{:08x}", self.value);
+        }
+        let width = (32 - (!self.mask).leading_zeros()).max(32 - self.value.leading_zeros());
+        f.write_str("#")?;
+        for i in (0..width).rev() {
+            let c = if self.mask >> i & 1 == 0 {
+                'x'
+            } else if self.value >> i & 1 == 1 {
+                '1'
+            } else {
+                '0'
+            };
+            write!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Result<BitPattern> {
+        let xml = format!("<value>{}</value>", text);
+        BitPattern::parse(&Element::parse(xml.as_bytes()).unwrap())
+    }
+
+    #[test]
+    fn parse_format() {
+        let p = parse("#01x1").unwrap();
+        assert_eq!(
+            p,
+            BitPattern {
+                value: 0b0101,
+                mask: !0b0010,
+            }
+        );
+        assert_eq!(p.to_string(), "#1x1");
+        assert_eq!(parse("0b1X0").unwrap().to_string(), "#1x0");
+        assert_eq!(parse("#x").unwrap().to_string(), "#x");
+        assert_eq!(parse("0x12").unwrap(), BitPattern::from(0x12));
+        assert_eq!(parse("#0101").unwrap().to_string(), "0x00000005");
+        assert!(parse("#012").is_err());
+    }
+
+    #[test]
+    fn matches() {
+        let p = parse("#1x0x").unwrap();
+        let matching: Vec<_> = (0..16).filter(|raw| p.matches(*raw)).collect();
+        assert_eq!(matching, [0b1000, 0b1001, 0b1100, 0b1101]);
+        assert_eq!(p.count(4), 4);
+        assert_eq!(p.count(3), 0);
+        assert!(p.overlaps(&BitPattern::from(0b1100)));
+        assert!(!p.overlaps(&BitPattern::from(0b1010)));
+    }
+}
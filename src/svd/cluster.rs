@@ -1,4 +1,4 @@
-use core::ops::Deref;
+use core::ops::{Deref, DerefMut};
 use xmltree::Element;
 
 use crate::types::Parse;
@@ -8,7 +8,10 @@ use crate::elementext::ElementExt;
 #[cfg(feature = "unproven")]
 use crate::encode::Encode;
 use crate::error::*;
-use crate::svd::{clusterinfo::ClusterInfo, dimelement::DimElement};
+use crate::svd::{
+    clusterinfo::ClusterInfo,
+    dimelement::{replace_index, DimElement},
+};
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
@@ -28,6 +31,41 @@ impl Deref for Cluster {
     }
 }
 
+impl DerefMut for Cluster {
+    fn deref_mut(&mut self) -> &mut ClusterInfo {
+        match self {
+            Cluster::Single(info) => info,
+            Cluster::Array(info, _) => info,
+        }
+    }
+}
+
+impl Cluster {
+    /// Returns the concrete clusters described by this element
+    ///
+    /// For arrays, `%s` is replaced by each index in the name and description and the
+    /// address offset advances by `dimIncrement` per instance. Fails when an offset does not
+    /// fit in 32 bits.
+    pub fn expand(&self) -> Result<Vec<ClusterInfo>> {
+        Ok(match self {
+            Cluster::Single(info) => vec![info.clone()],
+            Cluster::Array(info, array_info) => array_info
+                .indexes()
+                .iter()
+                .zip(array_info.offsets(info.address_offset)?)
+                .map(|(index, address_offset)| {
+                    let mut cluster = info.clone();
+                    cluster.name = replace_index(&info.name, index);
+                    cluster.description =
+                        info.description.as_ref().map(|d| replace_index(d, index));
+                    cluster.address_offset = address_offset;
+                    cluster
+                })
+                .collect(),
+        })
+    }
+}
+
 impl Parse for Cluster {
     type Object = Cluster;
     type Error = anyhow::Error;
@@ -39,17 +77,7 @@ impl Parse for Cluster {
 
         if tree.get_child("dimIncrement").is_some() {
             let array_info = DimElement::parse(tree)?;
-            if !info.name.contains("%s") {
-                // TODO: replace with real error
-                anyhow::bail!("Cluster name invalid");
-            }
-
-            if let Some(indices) = &array_info.dim_index {
-                if array_info.dim as usize != indices.len() {
-                    // TODO: replace with real error
-                    anyhow::bail!("Cluster index length mismatch");
-                }
-            }
+            array_info.check(tree, &info.name, info.address_offset)?;
 
             Ok(Cluster::Array(info, array_info))
         } else {
@@ -76,3 +104,43 @@ impl Encode for Cluster {
 }
 
 // TODO: test Cluster encoding and decoding
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_array() {
+        let parse = |name: &str, address_offset: &str, dim: &str| {
+            let xml = format!(
+                "<cluster>
+                    <name>{}</name>
+                    <addressOffset>{}</addressOffset>
+                    <dim>{}</dim>
+                    <dimIncrement>0x20</dimIncrement>
+                    <dimIndex>A,B</dimIndex>
+                    <register>
+                        <name>CR</name>
+                        <addressOffset>0x0</addressOffset>
+                    </register>
+                </cluster>",
+                name, address_offset, dim
+            );
+            let err = Cluster::parse(&Element::parse(xml.as_bytes()).unwrap()).unwrap_err();
+            err.downcast::<SVDError>().unwrap()
+        };
+
+        assert!(matches!(
+            parse("CH", "0x10", "2"),
+            SVDError::MissingDimPlaceholder(_, name) if name == "CH"
+        ));
+        assert!(matches!(
+            parse("CH%s", "0x10", "3"),
+            SVDError::DimIndexMismatch(_, name, 3, 2) if name == "CH%s"
+        ));
+        assert_eq!(
+            parse("CH%s", "0xfffffff0", "2"),
+            SVDError::DimOverflow(0xffff_fff0, 2, 0x20)
+        );
+    }
+}
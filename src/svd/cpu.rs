@@ -9,19 +9,35 @@ use crate::encode::Encode;
 use crate::error::*;
 #[cfg(feature = "unproven")]
 use crate::new_element;
-use crate::svd::endian::Endian;
-use crate::types::Parse;
+use crate::parse;
+use crate::svd::{cpuname::CpuName, endian::Endian, protection::Protection};
+use crate::types::{BoolParse, Parse};
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
 pub struct Cpu {
-    pub name: String,
+    pub name: CpuName,
     pub revision: String,
     pub endian: Endian,
     pub mpu_present: bool,
     pub fpu_present: bool,
+    /// Whether the FPU supports double precision
+    pub fpu_double_precision: Option<bool>,
+    pub dsp_present: Option<bool>,
+    pub icache_present: Option<bool>,
+    pub dcache_present: Option<bool>,
+    pub itcm_present: Option<bool>,
+    pub dtcm_present: Option<bool>,
+    /// Whether the Vector Table Offset Register is implemented
+    pub vtor_present: Option<bool>,
     pub nvic_priority_bits: u32,
     pub has_vendor_systick: bool,
+    /// Number of device specific interrupts, excluding the core ones
+    pub device_num_interrupts: Option<u32>,
+    /// Number of regions of the Security Attribution Unit
+    pub sau_num_regions: Option<u32>,
+    /// Initial configuration of the Security Attribution Unit
+    pub sau_regions_config: Option<SauRegionsConfig>,
 
     // Reserve the right to add more fields to this struct
     pub(crate) _extensible: (),
@@ -37,13 +53,23 @@ impl Parse for Cpu {
         }
 
         Ok(Cpu {
-            name: tree.get_child_text("name")?,
+            name: CpuName::parse(tree.get_child_elem("name")?)?,
             revision: tree.get_child_text("revision")?,
             endian: Endian::parse(tree.get_child_elem("endian")?)?,
             mpu_present: tree.get_child_bool("mpuPresent")?,
             fpu_present: tree.get_child_bool("fpuPresent")?,
+            fpu_double_precision: parse::optional::<BoolParse>("fpuDP", tree)?,
+            dsp_present: parse::optional::<BoolParse>("dspPresent", tree)?,
+            icache_present: parse::optional::<BoolParse>("icachePresent", tree)?,
+            dcache_present: parse::optional::<BoolParse>("dcachePresent", tree)?,
+            itcm_present: parse::optional::<BoolParse>("itcmPresent", tree)?,
+            dtcm_present: parse::optional::<BoolParse>("dtcmPresent", tree)?,
+            vtor_present: parse::optional::<BoolParse>("vtorPresent", tree)?,
             nvic_priority_bits: tree.get_child_u32("nvicPrioBits")?,
             has_vendor_systick: tree.get_child_bool("vendorSystickConfig")?,
+            device_num_interrupts: parse::optional::<u32>("deviceNumInterrupts", tree)?,
+            sau_num_regions: parse::optional::<u32>("sauNumRegions", tree)?,
+            sau_regions_config: parse::optional::<SauRegionsConfig>("sauRegionsConfig", tree)?,
             _extensible: (),
         })
     }
@@ -54,32 +80,245 @@ impl Encode for Cpu {
     type Error = anyhow::Error;
 
     fn encode(&self) -> Result<Element> {
+        let mut children = vec![
+            self.name.encode()?,
+            new_element("revision", Some(self.revision.clone())),
+            self.endian.encode()?,
+            new_element("mpuPresent", Some(format!("{}", self.mpu_present))),
+            new_element("fpuPresent", Some(format!("{}", self.fpu_present))),
+        ];
+
+        let flags = [
+            ("fpuDP", self.fpu_double_precision),
+            ("dspPresent", self.dsp_present),
+            ("icachePresent", self.icache_present),
+            ("dcachePresent", self.dcache_present),
+            ("itcmPresent", self.itcm_present),
+            ("dtcmPresent", self.dtcm_present),
+            ("vtorPresent", self.vtor_present),
+        ];
+        for (name, v) in flags.iter() {
+            if let Some(v) = v {
+                children.push(new_element(name, Some(format!("{}", v))));
+            }
+        }
+
+        children.push(new_element(
+            "nvicPrioBits",
+            Some(format!("{}", self.nvic_priority_bits)),
+        ));
+        children.push(new_element(
+            "vendorSystickConfig",
+            Some(format!("{}", self.has_vendor_systick)),
+        ));
+
+        if let Some(v) = &self.device_num_interrupts {
+            children.push(new_element("deviceNumInterrupts", Some(format!("{}", v))));
+        }
+
+        if let Some(v) = &self.sau_num_regions {
+            children.push(new_element("sauNumRegions", Some(format!("{}", v))));
+        }
+
+        if let Some(v) = &self.sau_regions_config {
+            children.push(v.encode()?);
+        }
+
         Ok(Element {
             prefix: None,
             namespace: None,
             namespaces: None,
             name: String::from("cpu"),
             attributes: HashMap::new(),
+            children,
+            text: None,
+        })
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SauRegionsConfig {
+    /// Whether the SAU is enabled at reset
+    pub enabled: Option<bool>,
+    /// Protection of the whole memory while the SAU is disabled
+    pub protection_when_disabled: Option<Protection>,
+    pub regions: Vec<SauRegion>,
+
+    // Reserve the right to add more fields to this struct
+    pub(crate) _extensible: (),
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct SauRegion {
+    pub enabled: Option<bool>,
+    pub name: Option<String>,
+    pub base: u32,
+    /// Last address of the region, its 5 lower bits are implied ones
+    pub limit: u32,
+    pub access: SauAccess,
+
+    // Reserve the right to add more fields to this struct
+    pub(crate) _extensible: (),
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SauAccess {
+    NonSecure,
+    NonSecureCallable,
+}
+
+/// Parses an optional boolean attribute
+fn bool_attribute(tree: &Element, name: &str) -> Result<Option<bool>> {
+    match tree.attributes.get(name) {
+        None => Ok(None),
+        Some(text) => match text.parse() {
+            Ok(b) => Ok(Some(b)),
+            Err(e) => Err(SVDError::InvalidBooleanValue(tree.clone(), text.clone(), e).into()),
+        },
+    }
+}
+
+impl Parse for SauRegionsConfig {
+    type Object = SauRegionsConfig;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<SauRegionsConfig> {
+        if tree.name != "sauRegionsConfig" {
+            return Err(SVDError::NameMismatch(tree.clone()).into());
+        }
+
+        let protection_when_disabled = match tree.attributes.get("protectionWhenDisabled") {
+            None => None,
+            Some(text) => Some(
+                Protection::parse_str(text)
+                    .ok_or_else(|| SVDError::UnknownProtection(tree.clone(), text.clone()))?,
+            ),
+        };
+        let regions: Result<Vec<_>, _> = tree
+            .children
+            .iter()
+            .filter(|t| t.name == "region")
+            .map(SauRegion::parse)
+            .collect();
+
+        Ok(SauRegionsConfig {
+            enabled: bool_attribute(tree, "enabled")?,
+            protection_when_disabled,
+            regions: regions?,
+            _extensible: (),
+        })
+    }
+}
+
+impl Parse for SauRegion {
+    type Object = SauRegion;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<SauRegion> {
+        Ok(SauRegion {
+            enabled: bool_attribute(tree, "enabled")?,
+            name: tree.attributes.get("name").cloned(),
+            base: tree.get_child_u32("base")?,
+            limit: tree.get_child_u32("limit")?,
+            access: SauAccess::parse(tree.get_child_elem("access")?)?,
+            _extensible: (),
+        })
+    }
+}
+
+impl Parse for SauAccess {
+    type Object = SauAccess;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<SauAccess> {
+        let text = tree.get_text()?;
+
+        match &text[..] {
+            "n" => Ok(SauAccess::NonSecure),
+            "c" => Ok(SauAccess::NonSecureCallable),
+            _ => Err(SVDError::UnknownSauAccess(tree.clone(), text).into()),
+        }
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Encode for SauRegionsConfig {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let mut attributes = HashMap::new();
+        if let Some(v) = &self.enabled {
+            attributes.insert(String::from("enabled"), format!("{}", v));
+        }
+        if let Some(v) = &self.protection_when_disabled {
+            attributes.insert(
+                String::from("protectionWhenDisabled"),
+                String::from(v.as_str()),
+            );
+        }
+
+        let regions: Result<Vec<_>, _> = self.regions.iter().map(SauRegion::encode).collect();
+        Ok(Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("sauRegionsConfig"),
+            attributes,
+            children: regions?,
+            text: None,
+        })
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Encode for SauRegion {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let mut attributes = HashMap::new();
+        if let Some(v) = &self.enabled {
+            attributes.insert(String::from("enabled"), format!("{}", v));
+        }
+        if let Some(v) = &self.name {
+            attributes.insert(String::from("name"), v.clone());
+        }
+
+        Ok(Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("region"),
+            attributes,
             children: vec![
-                new_element("name", Some(self.name.clone())),
-                new_element("revision", Some(self.revision.clone())),
-                self.endian.encode()?,
-                new_element("mpuPresent", Some(format!("{}", self.mpu_present))),
-                new_element("fpuPresent", Some(format!("{}", self.fpu_present))),
-                new_element("nvicPrioBits", Some(format!("{}", self.nvic_priority_bits))),
-                new_element(
-                    "vendorSystickConfig",
-                    Some(format!("{}", self.has_vendor_systick)),
-                ),
+                new_element("base", Some(format!("0x{:08X}", self.base))),
+                new_element("limit", Some(format!("0x{:08X}", self.limit))),
+                self.access.encode()?,
             ],
             text: None,
         })
     }
 }
 
+#[cfg(feature = "unproven")]
+impl Encode for SauAccess {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let text = match *self {
+            SauAccess::NonSecure => String::from("n"),
+            SauAccess::NonSecureCallable => String::from("c"),
+        };
+
+        Ok(new_element("access", Some(text)))
+    }
+}
+
 impl Cpu {
     pub fn is_cortex_m(&self) -> bool {
-        self.name.starts_with("CM")
+        self.name.is_cortex_m()
     }
 }
 
@@ -91,18 +330,29 @@ mod tests {
 
     #[test]
     fn decode_encode() {
-        let tests = vec![(
-            Cpu {
-                name: String::from("EFM32JG12B500F512GM48"),
-                revision: String::from("5.1.1"),
-                endian: Endian::Little,
-                mpu_present: true,
-                fpu_present: true,
-                nvic_priority_bits: 8,
-                has_vendor_systick: false,
-                _extensible: (),
-            },
-            "
+        let tests = vec![
+            (
+                Cpu {
+                    name: CpuName::Other(String::from("EFM32JG12B500F512GM48")),
+                    revision: String::from("5.1.1"),
+                    endian: Endian::Little,
+                    mpu_present: true,
+                    fpu_present: true,
+                    fpu_double_precision: None,
+                    dsp_present: None,
+                    icache_present: None,
+                    dcache_present: None,
+                    itcm_present: None,
+                    dtcm_present: None,
+                    vtor_present: None,
+                    nvic_priority_bits: 8,
+                    has_vendor_systick: false,
+                    device_num_interrupts: None,
+                    sau_num_regions: None,
+                    sau_regions_config: None,
+                    _extensible: (),
+                },
+                "
                     <cpu>
                         <name>EFM32JG12B500F512GM48</name>  
                         <revision>5.1.1</revision>
@@ -113,8 +363,129 @@ mod tests {
                         <vendorSystickConfig>false</vendorSystickConfig>
                     </cpu>
                 ",
-        )];
+            ),
+            (
+                Cpu {
+                    name: CpuName::CortexM33,
+                    revision: String::from("r0p4"),
+                    endian: Endian::Little,
+                    mpu_present: true,
+                    fpu_present: true,
+                    fpu_double_precision: Some(false),
+                    dsp_present: Some(true),
+                    icache_present: Some(false),
+                    dcache_present: Some(false),
+                    itcm_present: Some(false),
+                    dtcm_present: Some(false),
+                    vtor_present: Some(true),
+                    nvic_priority_bits: 3,
+                    has_vendor_systick: false,
+                    device_num_interrupts: Some(65),
+                    sau_num_regions: Some(8),
+                    sau_regions_config: None,
+                    _extensible: (),
+                },
+                "
+                    <cpu>
+                        <name>CM33</name>
+                        <revision>r0p4</revision>
+                        <endian>little</endian>
+                        <mpuPresent>true</mpuPresent>
+                        <fpuPresent>true</fpuPresent>
+                        <fpuDP>false</fpuDP>
+                        <dspPresent>true</dspPresent>
+                        <icachePresent>false</icachePresent>
+                        <dcachePresent>false</dcachePresent>
+                        <itcmPresent>false</itcmPresent>
+                        <dtcmPresent>false</dtcmPresent>
+                        <vtorPresent>true</vtorPresent>
+                        <nvicPrioBits>3</nvicPrioBits>
+                        <vendorSystickConfig>false</vendorSystickConfig>
+                        <deviceNumInterrupts>65</deviceNumInterrupts>
+                        <sauNumRegions>8</sauNumRegions>
+                    </cpu>
+                ",
+            ),
+            (
+                Cpu {
+                    name: CpuName::CortexM0Plus,
+                    revision: String::from("r0p1"),
+                    endian: Endian::Little,
+                    mpu_present: false,
+                    fpu_present: false,
+                    fpu_double_precision: None,
+                    dsp_present: None,
+                    icache_present: None,
+                    dcache_present: None,
+                    itcm_present: None,
+                    dtcm_present: None,
+                    vtor_present: None,
+                    nvic_priority_bits: 2,
+                    has_vendor_systick: false,
+                    device_num_interrupts: None,
+                    sau_num_regions: None,
+                    sau_regions_config: None,
+                    _extensible: (),
+                },
+                "
+                    <cpu>
+                        <name>CM0PLUS</name>
+                        <revision>r0p1</revision>
+                        <endian>little</endian>
+                        <mpuPresent>false</mpuPresent>
+                        <fpuPresent>false</fpuPresent>
+                        <nvicPrioBits>2</nvicPrioBits>
+                        <vendorSystickConfig>false</vendorSystickConfig>
+                    </cpu>
+                ",
+            ),
+        ];
 
         run_test::<Cpu>(&tests[..]);
     }
+
+    #[test]
+    fn decode_encode_sau() {
+        let tests = vec![(
+            SauRegionsConfig {
+                enabled: Some(true),
+                protection_when_disabled: Some(Protection::Secure),
+                regions: vec![
+                    SauRegion {
+                        enabled: Some(true),
+                        name: Some(String::from("SAU1")),
+                        base: 0x1000_1000,
+                        limit: 0x1000_501F,
+                        access: SauAccess::NonSecure,
+                        _extensible: (),
+                    },
+                    SauRegion {
+                        enabled: None,
+                        name: None,
+                        base: 0x2000_0000,
+                        limit: 0x2000_001F,
+                        access: SauAccess::NonSecureCallable,
+                        _extensible: (),
+                    },
+                ],
+                _extensible: (),
+            },
+            "
+                <sauRegionsConfig enabled=\"true\" protectionWhenDisabled=\"s\">
+                    <region enabled=\"true\" name=\"SAU1\">
+                        <base>0x10001000</base>
+                        <limit>0x1000501F</limit>
+                        <access>n</access>
+                    </region>
+                    <region>
+                        <base>0x20000000</base>
+                        <limit>0x2000001F</limit>
+                        <access>c</access>
+                    </region>
+                </sauRegionsConfig>
+            ",
+        )];
+
+        run_test::<SauRegionsConfig>(&tests[..]);
+    }
 }
@@ -0,0 +1,236 @@
+use core::fmt;
+
+use xmltree::Element;
+
+use crate::elementext::ElementExt;
+#[cfg(feature = "unproven")]
+use crate::encode::Encode;
+use crate::error::*;
+#[cfg(feature = "unproven")]
+use crate::new_element;
+use crate::types::Parse;
+
+/// Processor core of a device, as named by the schema `cpuNameType`
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CpuName {
+    CortexM0,
+    /// Named either `CM0PLUS` or `CM0+`, encoded as `CM0PLUS`
+    CortexM0Plus,
+    CortexM1,
+    SecurCoreSC000,
+    CortexM23,
+    CortexM3,
+    CortexM33,
+    CortexM35P,
+    CortexM55,
+    SecurCoreSC300,
+    CortexM4,
+    CortexM7,
+    CortexA5,
+    CortexA7,
+    CortexA8,
+    CortexA9,
+    CortexA15,
+    CortexA17,
+    CortexA53,
+    CortexA57,
+    CortexA72,
+    /// Any other core, such as `other` or a vendor specific name
+    Other(String),
+}
+
+/// Architecture implemented by a processor core
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArchVersion {
+    /// Armv6-M
+    V6M,
+    /// Armv7-M
+    V7M,
+    /// Armv7E-M, Armv7-M with the DSP extension
+    V7EM,
+    /// Armv8-M Baseline
+    V8MBaseline,
+    /// Armv8-M Mainline
+    V8MMainline,
+    /// Armv8.1-M Mainline
+    V81MMainline,
+    /// Armv7-A
+    V7A,
+    /// Armv8-A
+    V8A,
+}
+
+impl CpuName {
+    pub(crate) fn parse_str(s: &str) -> CpuName {
+        match s {
+            "CM0" => CpuName::CortexM0,
+            "CM0PLUS" | "CM0+" => CpuName::CortexM0Plus,
+            "CM1" => CpuName::CortexM1,
+            "SC000" => CpuName::SecurCoreSC000,
+            "CM23" => CpuName::CortexM23,
+            "CM3" => CpuName::CortexM3,
+            "CM33" => CpuName::CortexM33,
+            "CM35P" => CpuName::CortexM35P,
+            "CM55" => CpuName::CortexM55,
+            "SC300" => CpuName::SecurCoreSC300,
+            "CM4" => CpuName::CortexM4,
+            "CM7" => CpuName::CortexM7,
+            "CA5" => CpuName::CortexA5,
+            "CA7" => CpuName::CortexA7,
+            "CA8" => CpuName::CortexA8,
+            "CA9" => CpuName::CortexA9,
+            "CA15" => CpuName::CortexA15,
+            "CA17" => CpuName::CortexA17,
+            "CA53" => CpuName::CortexA53,
+            "CA57" => CpuName::CortexA57,
+            "CA72" => CpuName::CortexA72,
+            _ => CpuName::Other(s.to_string()),
+        }
+    }
+
+    /// Name of the core as written in SVD files
+    pub fn as_str(&self) -> &str {
+        match self {
+            CpuName::CortexM0 => "CM0",
+            CpuName::CortexM0Plus => "CM0PLUS",
+            CpuName::CortexM1 => "CM1",
+            CpuName::SecurCoreSC000 => "SC000",
+            CpuName::CortexM23 => "CM23",
+            CpuName::CortexM3 => "CM3",
+            CpuName::CortexM33 => "CM33",
+            CpuName::CortexM35P => "CM35P",
+            CpuName::CortexM55 => "CM55",
+            CpuName::SecurCoreSC300 => "SC300",
+            CpuName::CortexM4 => "CM4",
+            CpuName::CortexM7 => "CM7",
+            CpuName::CortexA5 => "CA5",
+            CpuName::CortexA7 => "CA7",
+            CpuName::CortexA8 => "CA8",
+            CpuName::CortexA9 => "CA9",
+            CpuName::CortexA15 => "CA15",
+            CpuName::CortexA17 => "CA17",
+            CpuName::CortexA53 => "CA53",
+            CpuName::CortexA57 => "CA57",
+            CpuName::CortexA72 => "CA72",
+            CpuName::Other(name) => name,
+        }
+    }
+
+    /// Architecture implemented by the core, `None` for unknown cores
+    pub fn arch_version(&self) -> Option<ArchVersion> {
+        Some(match self {
+            CpuName::CortexM0
+            | CpuName::CortexM0Plus
+            | CpuName::CortexM1
+            | CpuName::SecurCoreSC000 => ArchVersion::V6M,
+            CpuName::CortexM3 | CpuName::SecurCoreSC300 => ArchVersion::V7M,
+            CpuName::CortexM4 | CpuName::CortexM7 => ArchVersion::V7EM,
+            CpuName::CortexM23 => ArchVersion::V8MBaseline,
+            CpuName::CortexM33 | CpuName::CortexM35P => ArchVersion::V8MMainline,
+            CpuName::CortexM55 => ArchVersion::V81MMainline,
+            CpuName::CortexA5
+            | CpuName::CortexA7
+            | CpuName::CortexA8
+            | CpuName::CortexA9
+            | CpuName::CortexA15
+            | CpuName::CortexA17 => ArchVersion::V7A,
+            CpuName::CortexA53 | CpuName::CortexA57 | CpuName::CortexA72 => ArchVersion::V8A,
+            CpuName::Other(_) => return None,
+        })
+    }
+
+    /// Whether the core is a Cortex-M, SecurCore ones excluded
+    pub fn is_cortex_m(&self) -> bool {
+        match self {
+            CpuName::SecurCoreSC000 | CpuName::SecurCoreSC300 => false,
+            _ => self.arch_version().is_some_and(ArchVersion::is_m_profile),
+        }
+    }
+
+    /// Whether the architecture of the core provides the TrustZone security extension
+    ///
+    /// The extension is optional on Armv8-M cores, see `Cpu::sau_num_regions`.
+    pub fn has_trustzone(&self) -> bool {
+        matches!(
+            self.arch_version(),
+            Some(ArchVersion::V8MBaseline)
+                | Some(ArchVersion::V8MMainline)
+                | Some(ArchVersion::V81MMainline)
+                | Some(ArchVersion::V7A)
+                | Some(ArchVersion::V8A)
+        )
+    }
+}
+
+impl ArchVersion {
+    /// Whether this is a microcontroller profile architecture
+    pub fn is_m_profile(self) -> bool {
+        !matches!(self, ArchVersion::V7A | ArchVersion::V8A)
+    }
+}
+
+impl fmt::Display for CpuName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Parse for CpuName {
+    type Object = CpuName;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<CpuName> {
+        Ok(CpuName::parse_str(&tree.get_text()?))
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Encode for CpuName {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        Ok(new_element("name", Some(String::from(self.as_str()))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capabilities() {
+        let m0 = CpuName::parse_str("CM0+");
+        assert_eq!(m0, CpuName::CortexM0Plus);
+        assert_eq!(m0.arch_version(), Some(ArchVersion::V6M));
+        assert!(m0.is_cortex_m());
+        assert!(!m0.has_trustzone());
+
+        let m33 = CpuName::parse_str("CM33");
+        assert_eq!(m33.arch_version(), Some(ArchVersion::V8MMainline));
+        assert!(m33.has_trustzone());
+
+        assert!(!CpuName::SecurCoreSC300.is_cortex_m());
+        assert!(!CpuName::CortexA53.is_cortex_m());
+
+        let other = CpuName::parse_str("CMSIS_X");
+        assert_eq!(other, CpuName::Other(String::from("CMSIS_X")));
+        assert_eq!(other.arch_version(), None);
+        assert!(!other.is_cortex_m());
+    }
+
+    #[cfg(feature = "unproven")]
+    #[test]
+    fn decode_encode() {
+        use crate::run_test;
+
+        let tests = [
+            (CpuName::CortexM0Plus, "<name>CM0PLUS</name>"),
+            (CpuName::CortexM7, "<name>CM7</name>"),
+            (CpuName::CortexA72, "<name>CA72</name>"),
+            (CpuName::Other(String::from("other")), "<name>other</name>"),
+        ];
+
+        run_test::<CpuName>(&tests[..]);
+    }
+}
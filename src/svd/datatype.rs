@@ -0,0 +1,125 @@
+use crate::elementext::ElementExt;
+#[cfg(feature = "unproven")]
+use std::collections::HashMap;
+use xmltree::Element;
+
+use crate::types::Parse;
+
+#[cfg(feature = "unproven")]
+use crate::encode::Encode;
+use crate::error::*;
+
+/// C type used for a register in generated headers
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DataType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I8,
+    I16,
+    I32,
+    I64,
+    U8Ptr,
+    U16Ptr,
+    U32Ptr,
+    U64Ptr,
+    I8Ptr,
+    I16Ptr,
+    I32Ptr,
+    I64Ptr,
+}
+
+impl DataType {
+    pub(crate) fn parse_str(s: &str) -> Option<Self> {
+        use self::DataType::*;
+        Some(match s {
+            "uint8_t" => U8,
+            "uint16_t" => U16,
+            "uint32_t" => U32,
+            "uint64_t" => U64,
+            "int8_t" => I8,
+            "int16_t" => I16,
+            "int32_t" => I32,
+            "int64_t" => I64,
+            "uint8_t *" => U8Ptr,
+            "uint16_t *" => U16Ptr,
+            "uint32_t *" => U32Ptr,
+            "uint64_t *" => U64Ptr,
+            "int8_t *" => I8Ptr,
+            "int16_t *" => I16Ptr,
+            "int32_t *" => I32Ptr,
+            "int64_t *" => I64Ptr,
+            _ => return None,
+        })
+    }
+
+    /// The C spelling of this type
+    pub fn as_str(self) -> &'static str {
+        use self::DataType::*;
+        match self {
+            U8 => "uint8_t",
+            U16 => "uint16_t",
+            U32 => "uint32_t",
+            U64 => "uint64_t",
+            I8 => "int8_t",
+            I16 => "int16_t",
+            I32 => "int32_t",
+            I64 => "int64_t",
+            U8Ptr => "uint8_t *",
+            U16Ptr => "uint16_t *",
+            U32Ptr => "uint32_t *",
+            U64Ptr => "uint64_t *",
+            I8Ptr => "int8_t *",
+            I16Ptr => "int16_t *",
+            I32Ptr => "int32_t *",
+            I64Ptr => "int64_t *",
+        }
+    }
+}
+
+impl Parse for DataType {
+    type Object = DataType;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<DataType> {
+        let text = tree.get_text()?;
+
+        Self::parse_str(&text).ok_or_else(|| SVDError::InvalidDataType(tree.clone(), text).into())
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Encode for DataType {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        Ok(Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("dataType"),
+            attributes: HashMap::new(),
+            children: vec![],
+            text: Some(self.as_str().into()),
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unproven")]
+mod tests {
+    use super::*;
+    use crate::run_test;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [
+            (DataType::U16, "<dataType>uint16_t</dataType>"),
+            (DataType::I32Ptr, "<dataType>int32_t *</dataType>"),
+        ];
+
+        run_test::<DataType>(&tests[..]);
+    }
+}
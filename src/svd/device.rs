@@ -1,6 +1,7 @@
 use crate::elementext::ElementExt;
 #[cfg(feature = "unproven")]
 use std::collections::HashMap;
+use std::collections::HashSet;
 use xmltree::Element;
 
 use crate::parse;
@@ -9,22 +10,40 @@ use crate::types::Parse;
 #[cfg(feature = "unproven")]
 use crate::encode::{Encode, EncodeChildren};
 use crate::error::*;
+use crate::extension::Extensions;
+use crate::iter::RegisterIter;
 #[cfg(feature = "unproven")]
 use crate::new_element;
-use crate::svd::{cpu::Cpu, peripheral::Peripheral, registerproperties::RegisterProperties};
+use crate::svd::{
+    cpu::Cpu, interrupt::Interrupt, peripheral::Peripheral, registercluster::RegisterCluster,
+    registerproperties::RegisterProperties,
+};
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Device {
+    pub vendor: Option<String>,
+    pub vendor_id: Option<String>,
     pub name: String,
+    pub series: Option<String>,
     schema_version: Option<String>,
     pub version: Option<String>,
     pub description: Option<String>,
+    pub license_text: Option<String>,
+    pub header_system_filename: Option<String>,
+    pub header_definitions_prefix: Option<String>,
     pub address_unit_bits: Option<u32>,
     pub width: Option<u32>,
     pub cpu: Option<Cpu>,
     pub peripherals: Vec<Peripheral>,
     pub default_register_properties: RegisterProperties,
+    /// `<vendorExtensions>` subtree, kept as is but for the children parsed into `extensions`.
+    /// Serialized as an XML string
+    #[cfg_attr(feature = "serde", serde(default, with = "vendor_extensions"))]
+    pub vendor_extensions: Option<Element>,
+    /// Vendor extensions parsed by `parse_with`, not serialized
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub extensions: Extensions,
     // Reserve the right to add more fields to this struct
     _extensible: (),
 }
@@ -36,13 +55,19 @@ impl Parse for Device {
     /// Parses a SVD file
     fn parse(tree: &Element) -> Result<Device> {
         Ok(Device {
+            vendor: tree.get_child_text_opt("vendor")?,
+            vendor_id: tree.get_child_text_opt("vendorID")?,
             name: tree.get_child_text("name")?,
+            series: tree.get_child_text_opt("series")?,
             schema_version: tree.attributes.get("schemaVersion").cloned(),
             cpu: parse::optional::<Cpu>("cpu", tree)?,
             version: tree.get_child_text_opt("version")?,
             description: tree.get_child_text_opt("description")?,
+            license_text: tree.get_child_text_opt("licenseText")?,
+            header_system_filename: tree.get_child_text_opt("headerSystemFilename")?,
+            header_definitions_prefix: tree.get_child_text_opt("headerDefinitionsPrefix")?,
             address_unit_bits: parse::optional::<u32>("addressUnitBits", tree)?,
-            width: None,
+            width: parse::optional::<u32>("width", tree)?,
             peripherals: {
                 let ps: Result<Vec<_>, _> = tree
                     .get_child_elem("peripherals")?
@@ -53,11 +78,153 @@ impl Parse for Device {
                 ps?
             },
             default_register_properties: RegisterProperties::parse(tree)?,
+            vendor_extensions: tree.get_child("vendorExtensions").cloned(),
+            extensions: Extensions::default(),
             _extensible: (),
         })
     }
 }
 
+#[cfg(feature = "serde")]
+mod vendor_extensions {
+    use serde::{de, ser, Deserialize, Deserializer, Serialize, Serializer};
+    use xmltree::Element;
+
+    pub fn serialize<S>(element: &Option<Element>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let xml = match element {
+            Some(element) => {
+                let mut buf = Vec::new();
+                element.write(&mut buf).map_err(ser::Error::custom)?;
+                Some(String::from_utf8(buf).map_err(ser::Error::custom)?)
+            }
+            None => None,
+        };
+        xml.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<Option<Element>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(xml) => Element::parse(xml.as_bytes())
+                .map(Some)
+                .map_err(de::Error::custom),
+            None => Ok(None),
+        }
+    }
+}
+
+impl Device {
+    /// Most interrupts an NVIC supports, bounding the vector table built by `interrupts`
+    pub const MAX_INTERRUPTS: u32 = 496;
+
+    /// Iterates over all registers of all peripherals, see `Peripheral::registers`
+    ///
+    /// Register properties cascade from the device defaults.
+    pub fn registers(&self) -> RegisterIter<'_> {
+        RegisterIter::new(
+            self.peripherals
+                .iter()
+                .map(|p| (p, self.default_register_properties)),
+        )
+    }
+
+    /// Builds the interrupt vector table of the device
+    ///
+    /// The table is indexed by interrupt number and has `None` gaps for unused numbers.
+    /// Interrupts declared several times under the same name, such as ones inherited by
+    /// `derivedFrom` peripherals, appear once. When several names share a number the first
+    /// declared one is kept, see `validate::interrupts` to report such conflicts.
+    ///
+    /// Interrupts numbered at or above the `deviceNumInterrupts` of the CPU, or above
+    /// `MAX_INTERRUPTS`, are left out.
+    pub fn interrupts(&self) -> Vec<Option<Interrupt>> {
+        let limit = self
+            .cpu
+            .as_ref()
+            .and_then(|cpu| cpu.device_num_interrupts)
+            .map_or(Self::MAX_INTERRUPTS, |n| n.min(Self::MAX_INTERRUPTS));
+        let mut table = Vec::new();
+        let mut names = HashSet::new();
+        for (_, interrupt) in self.peripheral_interrupts() {
+            if interrupt.value >= limit || !names.insert(&interrupt.name) {
+                continue;
+            }
+            let value = interrupt.value as usize;
+            if table.len() <= value {
+                table.resize(value + 1, None);
+            }
+            if table[value].is_none() {
+                table[value] = Some(interrupt.clone());
+            }
+        }
+        table
+    }
+
+    /// Interrupts of every peripheral along with the peripheral
+    ///
+    /// `derivedFrom` peripherals declaring no interrupt get the ones of their base.
+    pub(crate) fn peripheral_interrupts(&self) -> Vec<(&Peripheral, &Interrupt)> {
+        let mut interrupts = Vec::new();
+        for p in &self.peripherals {
+            let mut source = p;
+            // Bounded to stay clear of cyclic derivations
+            for _ in 0..self.peripherals.len() {
+                if !source.interrupt.is_empty() {
+                    break;
+                }
+                match source
+                    .derived_from
+                    .as_ref()
+                    .and_then(|base| self.peripherals.iter().find(|b| &b.name == base))
+                {
+                    Some(base) => source = base,
+                    None => break,
+                }
+            }
+            interrupts.extend(source.interrupt.iter().map(|i| (p, i)));
+        }
+        interrupts
+    }
+
+    /// Returns the effective properties of a register
+    ///
+    /// `path` is the dotted name of the register as declared in the SVD, such as `PERIPH.REG`
+    /// or `PERIPH.CLUSTER.REG`. Properties cascade from the device through the peripheral and
+    /// each enclosing cluster down to the register, the innermost value taking precedence.
+    /// `derivedFrom` references are not followed, see `derive_from::resolve`.
+    pub fn effective_properties(&self, path: &str) -> Option<RegisterProperties> {
+        let mut names = path.split('.').peekable();
+        let name = names.next()?;
+        let peripheral = self.peripherals.iter().find(|p| p.name == name)?;
+        let mut properties = peripheral
+            .default_register_properties
+            .inherit(&self.default_register_properties);
+        let mut children = peripheral.registers.as_ref()?;
+        loop {
+            let name = names.next()?;
+            if names.peek().is_none() {
+                return children.iter().find_map(|rc| match rc {
+                    RegisterCluster::Register(r) if r.name == name => {
+                        Some(r.properties().inherit(&properties))
+                    }
+                    _ => None,
+                });
+            }
+            let cluster = children.iter().find_map(|rc| match rc {
+                RegisterCluster::Cluster(c) if c.name == name => Some(c),
+                _ => None,
+            })?;
+            properties = cluster.default_register_properties.inherit(&properties);
+            children = &cluster.children;
+        }
+    }
+}
+
 #[cfg(feature = "unproven")]
 impl Encode for Device {
     type Error = anyhow::Error;
@@ -69,7 +236,7 @@ impl Encode for Device {
             namespaces: None,
             name: String::from("device"),
             attributes: HashMap::new(),
-            children: vec![new_element("name", Some(self.name.clone()))],
+            children: Vec::new(),
             text: None,
         };
 
@@ -88,6 +255,21 @@ impl Encode for Device {
             );
         }
 
+        if let Some(v) = &self.vendor {
+            elem.children.push(new_element("vendor", Some(v.clone())));
+        }
+
+        if let Some(v) = &self.vendor_id {
+            elem.children.push(new_element("vendorID", Some(v.clone())));
+        }
+
+        elem.children
+            .push(new_element("name", Some(self.name.clone())));
+
+        if let Some(v) = &self.series {
+            elem.children.push(new_element("series", Some(v.clone())));
+        }
+
         if let Some(v) = &self.version {
             elem.children.push(new_element("version", Some(v.clone())));
         }
@@ -97,7 +279,26 @@ impl Encode for Device {
                 .push(new_element("description", Some(v.clone())));
         }
 
-        if let Some(v) = &self.description {
+        if let Some(v) = &self.license_text {
+            elem.children
+                .push(new_element("licenseText", Some(v.clone())));
+        }
+
+        if let Some(v) = &self.cpu {
+            elem.children.push(v.encode()?);
+        }
+
+        if let Some(v) = &self.header_system_filename {
+            elem.children
+                .push(new_element("headerSystemFilename", Some(v.clone())));
+        }
+
+        if let Some(v) = &self.header_definitions_prefix {
+            elem.children
+                .push(new_element("headerDefinitionsPrefix", Some(v.clone())));
+        }
+
+        if let Some(v) = &self.address_unit_bits {
             elem.children
                 .push(new_element("addressUnitBits", Some(format!("{}", v))));
         }
@@ -110,10 +311,6 @@ impl Encode for Device {
         elem.children
             .extend(self.default_register_properties.encode()?);
 
-        if let Some(v) = &self.cpu {
-            elem.children.push(v.encode()?);
-        }
-
         let peripherals: Result<Vec<_>, _> =
             self.peripherals.iter().map(Peripheral::encode).collect();
         elem.children.push(Element {
@@ -126,8 +323,308 @@ impl Encode for Device {
             text: None,
         });
 
+        let mut vendor_extensions = self.vendor_extensions.clone();
+        if !self.extensions.is_empty() {
+            vendor_extensions
+                .get_or_insert_with(|| new_element("vendorExtensions", None))
+                .children
+                .extend(self.extensions.encode()?);
+        }
+        if let Some(v) = vendor_extensions {
+            elem.children.push(v);
+        }
+
         Ok(elem)
     }
 }
 
-// TODO: test device encoding and decoding
+#[cfg(test)]
+mod tests {
+    #[cfg(any(feature = "unproven", feature = "serde"))]
+    use super::*;
+    use crate::svd::{access::Access, protection::Protection};
+    #[cfg(feature = "unproven")]
+    use crate::{
+        encode::Encode,
+        svd::{cpuname::CpuName, endian::Endian},
+    };
+
+    #[cfg(feature = "unproven")]
+    #[test]
+    fn decode_encode() {
+        // The encoded tree carries namespace attributes, so compare parsed devices instead
+        // of trees as `run_test` does
+        let (expected, xml) = (
+            Device {
+                vendor: Some(String::from("Nordic Semiconductor")),
+                vendor_id: Some(String::from("Nordic")),
+                name: String::from("nrf52"),
+                series: Some(String::from("nRF52")),
+                schema_version: None,
+                version: Some(String::from("1")),
+                description: Some(String::from("nRF52832 reference description")),
+                license_text: Some(String::from("Copyright (c) Nordic Semiconductor")),
+                header_system_filename: Some(String::from("system_nrf52")),
+                header_definitions_prefix: Some(String::from("NRF_")),
+                address_unit_bits: Some(8),
+                width: Some(32),
+                cpu: Some(Cpu {
+                    name: CpuName::CortexM4,
+                    revision: String::from("r0p1"),
+                    endian: Endian::Little,
+                    mpu_present: true,
+                    fpu_present: true,
+                    fpu_double_precision: None,
+                    dsp_present: None,
+                    icache_present: None,
+                    dcache_present: None,
+                    itcm_present: None,
+                    dtcm_present: None,
+                    vtor_present: None,
+                    nvic_priority_bits: 3,
+                    has_vendor_systick: false,
+                    device_num_interrupts: None,
+                    sau_num_regions: None,
+                    sau_regions_config: None,
+                    _extensible: (),
+                }),
+                peripherals: Vec::new(),
+                default_register_properties: RegisterProperties {
+                    size: Some(32),
+                    reset_value: Some(0),
+                    reset_mask: Some(0xffff_ffff),
+                    access: Some(Access::ReadWrite),
+                    protection: None,
+                    _extensible: (),
+                },
+                vendor_extensions: Some(
+                    Element::parse(
+                        "<vendorExtensions><errata id=\"108\">RAM retention</errata></vendorExtensions>"
+                            .as_bytes(),
+                    )
+                    .unwrap(),
+                ),
+                extensions: Extensions::default(),
+                _extensible: (),
+            },
+            "
+            <device>
+                <vendor>Nordic Semiconductor</vendor>
+                <vendorID>Nordic</vendorID>
+                <name>nrf52</name>
+                <series>nRF52</series>
+                <version>1</version>
+                <description>nRF52832 reference description</description>
+                <licenseText>Copyright (c) Nordic Semiconductor</licenseText>
+                <cpu>
+                    <name>CM4</name>
+                    <revision>r0p1</revision>
+                    <endian>little</endian>
+                    <mpuPresent>true</mpuPresent>
+                    <fpuPresent>true</fpuPresent>
+                    <nvicPrioBits>3</nvicPrioBits>
+                    <vendorSystickConfig>false</vendorSystickConfig>
+                </cpu>
+                <headerSystemFilename>system_nrf52</headerSystemFilename>
+                <headerDefinitionsPrefix>NRF_</headerDefinitionsPrefix>
+                <addressUnitBits>8</addressUnitBits>
+                <width>32</width>
+                <size>32</size>
+                <access>read-write</access>
+                <resetValue>0x00000000</resetValue>
+                <resetMask>0xFFFFFFFF</resetMask>
+                <peripherals>
+                </peripherals>
+                <vendorExtensions><errata id=\"108\">RAM retention</errata></vendorExtensions>
+            </device>
+            ",
+        );
+
+        let device = Device::parse(&Element::parse(xml.as_bytes()).unwrap()).unwrap();
+        assert_eq!(device, expected);
+        let encoded = device.encode().unwrap();
+        assert_eq!(Device::parse(&encoded).unwrap(), expected);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_vendor_extensions() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <peripherals>
+                </peripherals>
+                <vendorExtensions>
+                    <errata id=\"108\">RAM retention</errata>
+                </vendorExtensions>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let json = serde_json::to_string(&device).unwrap();
+        let deserialized: Device = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, device);
+    }
+
+    #[test]
+    fn effective_properties() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <size>32</size>
+                <resetValue>0</resetValue>
+                <access>read-write</access>
+                <protection>n</protection>
+                <peripherals>
+                    <peripheral>
+                        <name>P</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <size>16</size>
+                        <registers>
+                            <register>
+                                <name>R</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                            <cluster>
+                                <name>C</name>
+                                <addressOffset>0x10</addressOffset>
+                                <access>read-only</access>
+                                <cluster>
+                                    <name>D</name>
+                                    <addressOffset>0x0</addressOffset>
+                                    <size>8</size>
+                                    <register>
+                                        <name>R</name>
+                                        <addressOffset>0x0</addressOffset>
+                                        <resetValue>0x12</resetValue>
+                                        <protection>s</protection>
+                                    </register>
+                                </cluster>
+                            </cluster>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let p = device.effective_properties("P.R").unwrap();
+        assert_eq!(p.size, Some(16));
+        assert_eq!(p.access, Some(Access::ReadWrite));
+        assert_eq!(p.protection, Some(Protection::NonSecure));
+
+        let p = device.effective_properties("P.C.D.R").unwrap();
+        assert_eq!(p.size, Some(8));
+        assert_eq!(p.access, Some(Access::ReadOnly));
+        assert_eq!(p.reset_value, Some(0x12));
+        assert_eq!(p.protection, Some(Protection::Secure));
+
+        assert_eq!(device.effective_properties("P.C"), None);
+        assert_eq!(device.effective_properties("P.C.R"), None);
+        assert_eq!(device.effective_properties("Q.R"), None);
+    }
+
+    #[test]
+    fn interrupt_table() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <peripherals>
+                    <peripheral>
+                        <name>TIM1</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <interrupt>
+                            <name>TIM1</name>
+                            <value>2</value>
+                        </interrupt>
+                    </peripheral>
+                    <peripheral derivedFrom=\"TIM1\">
+                        <name>TIM2</name>
+                        <baseAddress>0x40000400</baseAddress>
+                    </peripheral>
+                    <peripheral>
+                        <name>USART</name>
+                        <baseAddress>0x40001000</baseAddress>
+                        <interrupt>
+                            <name>USART</name>
+                            <value>5</value>
+                        </interrupt>
+                        <interrupt>
+                            <name>USART_WAKEUP</name>
+                            <value>0</value>
+                        </interrupt>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let inherited: Vec<_> = device
+            .peripheral_interrupts()
+            .iter()
+            .map(|(p, i)| (p.name.as_str(), i.name.as_str()))
+            .collect();
+        assert_eq!(
+            inherited,
+            [
+                ("TIM1", "TIM1"),
+                ("TIM2", "TIM1"),
+                ("USART", "USART"),
+                ("USART", "USART_WAKEUP")
+            ]
+        );
+
+        let table = device.interrupts();
+        let table: Vec<_> = table
+            .iter()
+            .map(|i| i.as_ref().map(|i| i.name.as_str()))
+            .collect();
+        assert_eq!(
+            table,
+            [
+                Some("USART_WAKEUP"),
+                None,
+                Some("TIM1"),
+                None,
+                None,
+                Some("USART")
+            ]
+        );
+    }
+
+    #[test]
+    fn interrupt_table_bounds() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <peripherals>
+                    <peripheral>
+                        <name>TIM1</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <interrupt>
+                            <name>TIM1</name>
+                            <value>1</value>
+                        </interrupt>
+                        <interrupt>
+                            <name>BOGUS</name>
+                            <value>0xFFFFFFFF</value>
+                        </interrupt>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let table = device.interrupts();
+        assert_eq!(table.len(), 2);
+        assert_eq!(table[1].as_ref().map(|i| i.name.as_str()), Some("TIM1"));
+    }
+}
@@ -0,0 +1,125 @@
+#[cfg(feature = "unproven")]
+use std::collections::HashMap;
+
+use crate::elementext::ElementExt;
+use xmltree::Element;
+
+#[cfg(feature = "unproven")]
+use crate::encode::Encode;
+use crate::error::*;
+#[cfg(feature = "unproven")]
+use crate::new_element;
+use crate::svd::enumeratedvalue::EnumeratedValue;
+use crate::types::Parse;
+
+/// Names of the indices of an array, used to generate an enumeration in headers
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct DimArrayIndex {
+    pub header_enum_name: Option<String>,
+    pub values: Vec<EnumeratedValue>,
+    // Reserve the right to add more fields to this struct
+    pub(crate) _extensible: (),
+}
+
+impl DimArrayIndex {
+    /// Name given to the array index `index`, if any
+    pub fn name_of(&self, index: u32) -> Option<&str> {
+        self.values
+            .iter()
+            .find(|v| v.value.is_some_and(|p| p.matches(index)))
+            .map(|v| v.name.as_str())
+    }
+}
+
+impl Parse for DimArrayIndex {
+    type Object = DimArrayIndex;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<DimArrayIndex> {
+        if tree.name != "dimArrayIndex" {
+            return Err(SVDError::NotExpectedTag(tree.clone(), "dimArrayIndex".to_string()).into());
+        }
+
+        let values: Result<Vec<_>, _> = tree
+            .children
+            .iter()
+            .filter(|t| t.name == "enumeratedValue")
+            .enumerate()
+            .map(|(e, t)| {
+                EnumeratedValue::parse(t)
+                    .with_context(|| format!("Parsing enumerated value #{}", e))
+            })
+            .collect();
+
+        Ok(DimArrayIndex {
+            header_enum_name: tree.get_child_text_opt("headerEnumName")?,
+            values: values?,
+            _extensible: (),
+        })
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Encode for DimArrayIndex {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let mut base = Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("dimArrayIndex"),
+            attributes: HashMap::new(),
+            children: vec![],
+            text: None,
+        };
+
+        if let Some(d) = &self.header_enum_name {
+            base.children
+                .push(new_element("headerEnumName", Some(d.clone())));
+        }
+
+        for v in &self.values {
+            base.children.push(v.encode()?);
+        }
+
+        Ok(base)
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unproven")]
+mod tests {
+    use super::*;
+    use crate::run_test;
+    use crate::svd::bitpattern::BitPattern;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [(
+            DimArrayIndex {
+                header_enum_name: Some(String::from("CLK_Enum")),
+                values: vec![EnumeratedValue {
+                    name: String::from("CPU"),
+                    description: None,
+                    value: Some(BitPattern::from(0)),
+                    is_default: None,
+                    _extensible: (),
+                }],
+                _extensible: (),
+            },
+            "
+            <dimArrayIndex>
+                <headerEnumName>CLK_Enum</headerEnumName>
+                <enumeratedValue>
+                    <name>CPU</name>
+                    <value>0x00000000</value>
+                </enumeratedValue>
+            </dimArrayIndex>
+            ",
+        )];
+
+        run_test::<DimArrayIndex>(&tests[..]);
+    }
+}
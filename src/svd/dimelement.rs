@@ -9,6 +9,7 @@ use crate::encode::Encode;
 use crate::new_element;
 
 use crate::error::*;
+use crate::svd::dimarrayindex::DimArrayIndex;
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
@@ -16,8 +17,10 @@ pub struct DimElement {
     pub dim: u32,
     pub dim_increment: u32,
     pub dim_index: Option<Vec<String>>,
+    pub dim_name: Option<String>,
+    pub dim_array_index: Option<DimArrayIndex>,
     // Reserve the right to add more fields to this struct
-    _extensible: (),
+    pub(crate) _extensible: (),
 }
 
 impl Parse for DimElement {
@@ -29,11 +32,80 @@ impl Parse for DimElement {
             dim: tree.get_child_u32("dim")?,
             dim_increment: tree.get_child_u32("dimIncrement")?,
             dim_index: parse_optional::<DimIndex>("dimIndex", tree)?,
+            dim_name: tree.get_child_text_opt("dimName")?,
+            dim_array_index: parse_optional::<DimArrayIndex>("dimArrayIndex", tree)?,
             _extensible: (),
         })
     }
 }
 
+impl DimElement {
+    /// Indices of the array instances
+    ///
+    /// Taken from `dimIndex`, otherwise from the names in `dimArrayIndex`, falling back to
+    /// counting from 0 for indices without a name.
+    pub fn indexes(&self) -> Vec<String> {
+        match (&self.dim_index, &self.dim_array_index) {
+            (Some(indexes), _) => indexes.clone(),
+            (None, Some(array_index)) => (0..self.dim)
+                .map(|i| {
+                    array_index
+                        .name_of(i)
+                        .map_or_else(|| i.to_string(), String::from)
+                })
+                .collect(),
+            (None, None) => (0..self.dim).map(|i| i.to_string()).collect(),
+        }
+    }
+
+    /// Offsets of the array instances, `dimIncrement` apart starting from `offset`
+    ///
+    /// Fails when an offset does not fit in 32 bits.
+    pub fn offsets(&self, offset: u32) -> Result<Vec<u32>> {
+        self.check_offsets(offset)?;
+        Ok((0..self.dim)
+            .map(|i| offset + i * self.dim_increment)
+            .collect())
+    }
+
+    /// Checks the array declared by `tree`, named `name` with instances starting at `offset`
+    ///
+    /// The name must have a `%s` placeholder, `dimIndex` must list `dim` indices and every
+    /// instance offset must fit in 32 bits.
+    pub(crate) fn check(&self, tree: &Element, name: &str, offset: u32) -> Result<()> {
+        if !name.contains("%s") {
+            return Err(SVDError::MissingDimPlaceholder(tree.clone(), name.to_string()).into());
+        }
+        if let Some(indices) = &self.dim_index {
+            if self.dim as usize != indices.len() {
+                return Err(SVDError::DimIndexMismatch(
+                    tree.clone(),
+                    name.to_string(),
+                    self.dim,
+                    indices.len(),
+                )
+                .into());
+            }
+        }
+        self.check_offsets(offset)
+    }
+
+    /// Checks that the offset of the last array instance fits in 32 bits, see `offsets`
+    fn check_offsets(&self, offset: u32) -> Result<()> {
+        self.dim
+            .saturating_sub(1)
+            .checked_mul(self.dim_increment)
+            .and_then(|o| o.checked_add(offset))
+            .map(|_| ())
+            .ok_or_else(|| SVDError::DimOverflow(offset, self.dim, self.dim_increment).into())
+    }
+}
+
+/// Substitutes an array index for the `%s` or `[%s]` placeholder of a name or description
+pub(crate) fn replace_index(s: &str, index: &str) -> String {
+    s.replace("[%s]", index).replace("%s", index)
+}
+
 #[cfg(feature = "unproven")]
 impl Encode for DimElement {
     type Error = anyhow::Error;
@@ -52,32 +124,106 @@ impl Encode for DimElement {
             e.children.push(new_element("dimIndex", Some(di.join(","))));
         }
 
+        if let Some(dn) = &self.dim_name {
+            e.children.push(new_element("dimName", Some(dn.clone())));
+        }
+
+        if let Some(dai) = &self.dim_array_index {
+            e.children.push(dai.encode()?);
+        }
+
         Ok(e)
     }
 }
 
 #[cfg(test)]
-#[cfg(feature = "unproven")]
 mod tests {
     use super::*;
-    use crate::run_test;
 
+    #[test]
+    fn indexes_from_dim_array_index() {
+        let tree = Element::parse(
+            "
+            <dimElement>
+                <dim>3</dim>
+                <dimIncrement>4</dimIncrement>
+                <dimArrayIndex>
+                    <headerEnumName>CLK_Enum</headerEnumName>
+                    <enumeratedValue>
+                        <name>CPU</name>
+                        <value>0</value>
+                    </enumeratedValue>
+                    <enumeratedValue>
+                        <name>BUS</name>
+                        <value>2</value>
+                    </enumeratedValue>
+                </dimArrayIndex>
+            </dimElement>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let dim = DimElement::parse(&tree).unwrap();
+
+        assert_eq!(dim.indexes(), ["CPU", "1", "BUS"]);
+    }
+
+    #[cfg(feature = "unproven")]
     #[test]
     fn decode_encode() {
-        let tests = vec![(
-            DimElement {
-                dim: 100,
-                dim_increment: 4,
-                dim_index: Some(vec!["10".to_owned(), "20".to_owned()]),
-                _extensible: (),
-            },
-            "<dimElement>
+        use crate::run_test;
+        use crate::svd::{bitpattern::BitPattern, enumeratedvalue::EnumeratedValue};
+
+        let tests = vec![
+            (
+                DimElement {
+                    dim: 100,
+                    dim_increment: 4,
+                    dim_index: Some(vec!["10".to_owned(), "20".to_owned()]),
+                    dim_name: None,
+                    dim_array_index: None,
+                    _extensible: (),
+                },
+                "<dimElement>
                 <dim>100</dim>
                 <dimIncrement>4</dimIncrement>
                 <dimIndex>10,20</dimIndex>
             </dimElement>
             ",
-        )];
+            ),
+            (
+                DimElement {
+                    dim: 2,
+                    dim_increment: 8,
+                    dim_index: None,
+                    dim_name: Some(String::from("CLK_TypeDef")),
+                    dim_array_index: Some(DimArrayIndex {
+                        header_enum_name: None,
+                        values: vec![EnumeratedValue {
+                            name: String::from("CPU"),
+                            description: None,
+                            value: Some(BitPattern::from(0)),
+                            is_default: None,
+                            _extensible: (),
+                        }],
+                        _extensible: (),
+                    }),
+                    _extensible: (),
+                },
+                "<dimElement>
+                <dim>2</dim>
+                <dimIncrement>8</dimIncrement>
+                <dimName>CLK_TypeDef</dimName>
+                <dimArrayIndex>
+                    <enumeratedValue>
+                        <name>CPU</name>
+                        <value>0x00000000</value>
+                    </enumeratedValue>
+                </dimArrayIndex>
+            </dimElement>
+            ",
+            ),
+        ];
 
         run_test::<DimElement>(&tests[..]);
     }
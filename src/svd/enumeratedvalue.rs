@@ -10,6 +10,7 @@ use crate::encode::Encode;
 use crate::error::*;
 #[cfg(feature = "unproven")]
 use crate::new_element;
+use crate::svd::bitpattern::BitPattern;
 use crate::types::Parse;
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -17,7 +18,7 @@ use crate::types::Parse;
 pub struct EnumeratedValue {
     pub name: String,
     pub description: Option<String>,
-    pub value: Option<u32>,
+    pub value: Option<BitPattern>,
     pub is_default: Option<bool>,
     // Reserve the right to add more fields to this struct
     pub(crate) _extensible: (),
@@ -29,7 +30,7 @@ impl EnumeratedValue {
             description: tree.get_child_text_opt("description")?,
             // TODO: this .ok() approach is simple, but does not expose errors parsing child objects.
             // Suggest refactoring all parse::type methods to return result so parse::optional works.
-            value: parse::optional::<u32>("value", tree)?,
+            value: parse::optional::<BitPattern>("value", tree)?,
             is_default: tree.get_child_bool("isDefault").ok(),
             _extensible: (),
         })
@@ -73,7 +74,7 @@ impl Encode for EnumeratedValue {
 
         if let Some(v) = &self.value {
             base.children
-                .push(new_element("value", Some(format!("0x{:08.x}", *v))));
+                .push(new_element("value", Some(v.to_string())));
         };
 
         if let Some(v) = &self.is_default {
@@ -99,7 +100,7 @@ mod tests {
                 description: Some(String::from(
                     "Zero wait-states inserted in fetch or read transfers",
                 )),
-                value: Some(0),
+                value: Some(BitPattern::from(0)),
                 is_default: Some(true),
                 _extensible: (),
             },
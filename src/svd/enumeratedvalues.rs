@@ -17,6 +17,7 @@ use crate::types::Parse;
 #[derive(Clone, Debug, PartialEq)]
 pub struct EnumeratedValues {
     pub name: Option<String>,
+    pub header_enum_name: Option<String>,
     pub usage: Option<Usage>,
     pub derived_from: Option<String>,
     pub values: Vec<EnumeratedValue>,
@@ -35,6 +36,7 @@ impl Parse for EnumeratedValues {
 
         Ok(EnumeratedValues {
             name: tree.get_child_text_opt("name")?,
+            header_enum_name: tree.get_child_text_opt("headerEnumName")?,
             usage: parse::optional::<Usage>("usage", tree)?,
             derived_from,
             values: {
@@ -70,6 +72,19 @@ impl Parse for EnumeratedValues {
     }
 }
 
+impl EnumeratedValues {
+    /// Returns the value matching a raw field value
+    ///
+    /// Don't-care bits of the values are ignored. The default value, if any, is returned
+    /// when no other value matches.
+    pub fn decode(&self, raw: u32) -> Option<&EnumeratedValue> {
+        self.values
+            .iter()
+            .find(|v| v.value.is_some_and(|p| p.matches(raw)))
+            .or_else(|| self.values.iter().find(|v| v.is_default == Some(true)))
+    }
+}
+
 #[cfg(feature = "unproven")]
 impl Encode for EnumeratedValues {
     type Error = anyhow::Error;
@@ -89,6 +104,11 @@ impl Encode for EnumeratedValues {
             base.children.push(new_element("name", Some((*d).clone())));
         };
 
+        if let Some(d) = &self.header_enum_name {
+            base.children
+                .push(new_element("headerEnumName", Some((*d).clone())));
+        };
+
         if let Some(v) = &self.usage {
             base.children.push(v.encode()?);
         };
@@ -110,12 +130,14 @@ impl Encode for EnumeratedValues {
 #[cfg(feature = "unproven")]
 mod tests {
     use super::*;
+    use crate::svd::bitpattern::BitPattern;
 
     #[test]
     fn decode_encode() {
         let example = String::from(
             "
             <enumeratedValues derivedFrom=\"fake-derivation.png\">
+                <headerEnumName>WaitStates</headerEnumName>
                 <enumeratedValue>
                     <name>WS0</name>
                     <description>Zero wait-states inserted in fetch or read transfers</description>
@@ -133,6 +155,7 @@ mod tests {
 
         let expected = EnumeratedValues {
             name: None,
+            header_enum_name: Some(String::from("WaitStates")),
             usage: None,
             derived_from: Some(String::from("fake-derivation.png")),
             values: vec![
@@ -141,7 +164,7 @@ mod tests {
                     description: Some(String::from(
                         "Zero wait-states inserted in fetch or read transfers",
                     )),
-                    value: Some(0),
+                    value: Some(BitPattern::from(0)),
                     is_default: Some(true),
                     _extensible: (),
                 },
@@ -150,7 +173,7 @@ mod tests {
                     description: Some(String::from(
                         "One wait-state inserted for each fetch or read transfer. See Flash Wait-States table for details",
                     )),
-                    value: Some(1),
+                    value: Some(BitPattern::from(1)),
                     is_default: None,
                     _extensible: (),
                 },
@@ -201,4 +224,38 @@ mod tests {
         parse(value.clone() + "<enumeratedValues></enumeratedValues>")
             .expect_err("<enumeratedValues> in invalid here");
     }
+
+    #[test]
+    fn decode() {
+        let tree = Element::parse(
+            "
+            <enumeratedValues>
+                <enumeratedValue>
+                    <name>OFF</name>
+                    <value>#00</value>
+                </enumeratedValue>
+                <enumeratedValue>
+                    <name>ON</name>
+                    <value>#1x</value>
+                </enumeratedValue>
+                <enumeratedValue>
+                    <name>OTHER</name>
+                    <isDefault>true</isDefault>
+                </enumeratedValue>
+            </enumeratedValues>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let values = EnumeratedValues::parse(&tree).unwrap();
+
+        let decoded: Vec<_> = (0..4)
+            .map(|raw| values.decode(raw).map(|v| v.name.as_str()))
+            .collect();
+        assert_eq!(
+            decoded,
+            [Some("OFF"), Some("OTHER"), Some("ON"), Some("ON")]
+        );
+        assert_eq!(values.values[1].value.unwrap().to_string(), "#1x");
+    }
 }
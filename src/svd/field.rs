@@ -1,4 +1,4 @@
-use core::ops::Deref;
+use core::ops::{Deref, DerefMut};
 
 use xmltree::Element;
 
@@ -9,7 +9,10 @@ use crate::elementext::ElementExt;
 #[cfg(feature = "unproven")]
 use crate::encode::Encode;
 use crate::error::*;
-use crate::svd::{dimelement::DimElement, fieldinfo::FieldInfo};
+use crate::svd::{
+    dimelement::{replace_index, DimElement},
+    fieldinfo::FieldInfo,
+};
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
@@ -29,6 +32,40 @@ impl Deref for Field {
     }
 }
 
+impl DerefMut for Field {
+    fn deref_mut(&mut self) -> &mut FieldInfo {
+        match self {
+            Field::Single(info) => info,
+            Field::Array(info, _) => info,
+        }
+    }
+}
+
+impl Field {
+    /// Returns the concrete fields described by this element
+    ///
+    /// For arrays, `%s` is replaced by each index in the name and description and the
+    /// bit offset advances by `dimIncrement` per instance. Fails when an offset does not fit
+    /// in 32 bits.
+    pub fn expand(&self) -> Result<Vec<FieldInfo>> {
+        Ok(match self {
+            Field::Single(info) => vec![info.clone()],
+            Field::Array(info, array_info) => array_info
+                .indexes()
+                .iter()
+                .zip(array_info.offsets(info.bit_range.offset)?)
+                .map(|(index, offset)| {
+                    let mut field = info.clone();
+                    field.name = replace_index(&info.name, index);
+                    field.description = info.description.as_ref().map(|d| replace_index(d, index));
+                    field.bit_range.offset = offset;
+                    field
+                })
+                .collect(),
+        })
+    }
+}
+
 impl Parse for Field {
     type Object = Field;
     type Error = anyhow::Error;
@@ -40,10 +77,7 @@ impl Parse for Field {
 
         if tree.get_child("dimIncrement").is_some() {
             let array_info = DimElement::parse(tree)?;
-            assert!(info.name.contains("%s"));
-            if let Some(indices) = &array_info.dim_index {
-                assert_eq!(array_info.dim as usize, indices.len())
-            }
+            array_info.check(tree, &info.name, info.bit_range.offset)?;
             Ok(Field::Array(info, array_info))
         } else {
             Ok(Field::Single(info))
@@ -59,13 +93,72 @@ impl Encode for Field {
         match self {
             Field::Single(info) => info.encode(),
             Field::Array(info, array_info) => {
-                // TODO: is this correct? probably not, need tests
-                let base = info.encode()?;
-                base.merge(&array_info.encode()?);
-                Ok(base)
+                let mut e = info.encode()?;
+                e = e.merge(&array_info.encode()?);
+                Ok(e)
             }
         }
     }
 }
 
 // TODO: add Field encode and decode tests
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand() {
+        let tree = Element::parse(
+            "
+            <field>
+                <name>EN%s</name>
+                <bitOffset>4</bitOffset>
+                <bitWidth>2</bitWidth>
+                <dim>2</dim>
+                <dimIncrement>2</dimIncrement>
+            </field>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let fields = Field::parse(&tree).unwrap().expand().unwrap();
+
+        let names: Vec<_> = fields.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["EN0", "EN1"]);
+        assert_eq!(fields[1].bit_range.lsb(), 6);
+        assert_eq!(fields[1].bit_range.msb(), 7);
+    }
+
+    #[test]
+    fn invalid_array() {
+        let parse = |name: &str, bit_offset: &str, dim: &str| {
+            let xml = format!(
+                "<field>
+                    <name>{}</name>
+                    <bitOffset>{}</bitOffset>
+                    <bitWidth>1</bitWidth>
+                    <dim>{}</dim>
+                    <dimIncrement>1</dimIncrement>
+                    <dimIndex>A,B</dimIndex>
+                </field>",
+                name, bit_offset, dim
+            );
+            let err = Field::parse(&Element::parse(xml.as_bytes()).unwrap()).unwrap_err();
+            err.downcast::<SVDError>().unwrap()
+        };
+
+        assert!(matches!(
+            parse("EN", "0", "2"),
+            SVDError::MissingDimPlaceholder(_, name) if name == "EN"
+        ));
+        assert!(matches!(
+            parse("EN%s", "0", "3"),
+            SVDError::DimIndexMismatch(_, name, 3, 2) if name == "EN%s"
+        ));
+        assert_eq!(
+            parse("EN%s", "0xffffffff", "2"),
+            SVDError::DimOverflow(0xffff_ffff, 2, 1)
+        );
+    }
+}
@@ -14,7 +14,8 @@ use crate::types::Parse;
 
 use crate::svd::{
     access::Access, bitrange::BitRange, enumeratedvalues::EnumeratedValues,
-    modifiedwritevalues::ModifiedWriteValues, writeconstraint::WriteConstraint,
+    modifiedwritevalues::ModifiedWriteValues, readaction::ReadAction,
+    writeconstraint::WriteConstraint,
 };
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -28,6 +29,7 @@ pub struct FieldInfo {
     pub enumerated_values: Vec<EnumeratedValues>,
     pub write_constraint: Option<WriteConstraint>,
     pub modified_write_values: Option<ModifiedWriteValues>,
+    pub read_action: Option<ReadAction>,
     // Reserve the right to add more fields to this struct
     pub(crate) _extensible: (),
 }
@@ -67,6 +69,7 @@ impl FieldInfo {
                 "modifiedWriteValues",
                 tree,
             )?,
+            read_action: parse::optional::<ReadAction>("readAction", tree)?,
             _extensible: (),
         })
     }
@@ -117,6 +120,10 @@ impl Encode for FieldInfo {
             elem.children.push(v.encode()?);
         };
 
+        if let Some(v) = &self.read_action {
+            elem.children.push(v.encode()?);
+        };
+
         Ok(elem)
     }
 }
@@ -126,7 +133,9 @@ impl Encode for FieldInfo {
 mod tests {
     use super::*;
     use crate::run_test;
-    use crate::svd::{bitrange::BitRangeType, enumeratedvalue::EnumeratedValue};
+    use crate::svd::{
+        bitpattern::BitPattern, bitrange::BitRangeType, enumeratedvalue::EnumeratedValue,
+    };
 
     #[test]
     fn decode_encode() {
@@ -144,6 +153,7 @@ mod tests {
                     access: Some(Access::ReadWrite),
                     enumerated_values: vec![EnumeratedValues {
                         name: None,
+                        header_enum_name: None,
                         usage: None,
                         derived_from: None,
                         values: vec![EnumeratedValue {
@@ -151,7 +161,7 @@ mod tests {
                             description: Some(String::from(
                                 "Zero wait-states inserted in fetch or read transfers",
                             )),
-                            value: Some(0),
+                            value: Some(BitPattern::from(0)),
                             is_default: None,
                             _extensible: (),
                         }],
@@ -159,6 +169,7 @@ mod tests {
                     }],
                     write_constraint: None,
                     modified_write_values: None,
+                    read_action: None,
                     _extensible: (),
                 },
                 "
@@ -192,6 +203,7 @@ mod tests {
                     enumerated_values: vec![],
                     write_constraint: None,
                     modified_write_values: None,
+                    read_action: Some(ReadAction::Set),
                     _extensible: (),
                 },
                 "
@@ -199,6 +211,7 @@ mod tests {
               <name>MODE</name>
               <bitOffset>24</bitOffset>
               <bitWidth>2</bitWidth>
+              <readAction>set</readAction>
             </field>
             ",
             ),
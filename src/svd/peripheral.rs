@@ -1,40 +1,117 @@
-#[cfg(feature = "unproven")]
-use std::collections::HashMap;
-
+use core::ops::{Deref, DerefMut, Range};
 use xmltree::Element;
 
-use crate::elementext::ElementExt;
-use crate::parse;
-
-#[cfg(feature = "unproven")]
-use crate::encode::{Encode, EncodeChildren};
-#[cfg(feature = "unproven")]
-use crate::new_element;
 use crate::types::Parse;
 
+#[cfg(feature = "unproven")]
+use crate::encode::Encode;
 use crate::error::*;
+use crate::iter::RegisterIter;
 use crate::svd::{
-    addressblock::AddressBlock, interrupt::Interrupt, registercluster::RegisterCluster,
+    addressblock::AddressBlockUsage,
+    dimelement::{replace_index, DimElement},
+    peripheralinfo::PeripheralInfo,
     registerproperties::RegisterProperties,
 };
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
-#[derive(Clone, Debug)]
-pub struct Peripheral {
-    pub name: String,
-    pub version: Option<String>,
-    pub display_name: Option<String>,
-    pub group_name: Option<String>,
-    pub description: Option<String>,
-    pub base_address: u32,
-    pub address_block: Option<AddressBlock>,
-    pub interrupt: Vec<Interrupt>,
-    pub default_register_properties: RegisterProperties,
-    /// `None` indicates that the `<registers>` node is not present
-    pub registers: Option<Vec<RegisterCluster>>,
-    pub derived_from: Option<String>,
-    // Reserve the right to add more fields to this struct
-    _extensible: (),
+#[derive(Clone, Debug, PartialEq)]
+pub enum Peripheral {
+    Single(PeripheralInfo),
+    Array(PeripheralInfo, DimElement),
+}
+
+impl Deref for Peripheral {
+    type Target = PeripheralInfo;
+
+    fn deref(&self) -> &PeripheralInfo {
+        match self {
+            Peripheral::Single(info) => info,
+            Peripheral::Array(info, _) => info,
+        }
+    }
+}
+
+impl DerefMut for Peripheral {
+    fn deref_mut(&mut self) -> &mut PeripheralInfo {
+        match self {
+            Peripheral::Single(info) => info,
+            Peripheral::Array(info, _) => info,
+        }
+    }
+}
+
+impl Peripheral {
+    /// Returns the concrete peripherals described by this element
+    ///
+    /// For arrays, `%s` is replaced by each index in the name, display name and description
+    /// and the base address advances by `dimIncrement` per instance. Fails when a base address
+    /// does not fit in 32 bits.
+    pub fn expand(&self) -> Result<Vec<PeripheralInfo>> {
+        Ok(match self {
+            Peripheral::Single(info) => vec![info.clone()],
+            Peripheral::Array(info, array_info) => array_info
+                .indexes()
+                .iter()
+                .zip(self.instances()?)
+                .map(|(index, (name, base_address))| {
+                    let mut peripheral = info.clone();
+                    peripheral.name = name;
+                    peripheral.display_name =
+                        info.display_name.as_ref().map(|d| replace_index(d, index));
+                    peripheral.description =
+                        info.description.as_ref().map(|d| replace_index(d, index));
+                    peripheral.base_address = base_address;
+                    peripheral
+                })
+                .collect(),
+        })
+    }
+
+    /// Names and base addresses of the concrete peripherals, see `expand`
+    pub(crate) fn instances(&self) -> Result<Vec<(String, u32)>> {
+        Ok(match self {
+            Peripheral::Single(info) => vec![(info.name.clone(), info.base_address)],
+            Peripheral::Array(info, array_info) => array_info
+                .indexes()
+                .iter()
+                .map(|index| replace_index(&info.name, index))
+                .zip(array_info.offsets(info.base_address)?)
+                .collect(),
+        })
+    }
+
+    /// Address ranges of the address blocks of every instance, along with the instance name
+    ///
+    /// Ranges are sorted by start address. Reserved blocks are left out, being no part of
+    /// the space the peripheral occupies. Fails when a base address does not fit in 32 bits.
+    pub fn address_ranges(&self) -> Result<Vec<(String, Range<u64>)>> {
+        let mut ranges: Vec<_> = self
+            .instances()?
+            .into_iter()
+            .flat_map(|(name, base_address)| {
+                self.address_block
+                    .iter()
+                    .filter(|block| block.usage != AddressBlockUsage::Reserved)
+                    .map(move |block| {
+                        let start = u64::from(base_address) + u64::from(block.offset);
+                        (name.clone(), start..start + u64::from(block.size))
+                    })
+            })
+            .collect();
+        ranges.sort_by_key(|(_, r)| r.start);
+        Ok(ranges)
+    }
+
+    /// Iterates over the registers of this peripheral
+    ///
+    /// Clusters are walked recursively and arrays expanded, including peripheral arrays.
+    /// Each item carries its path and absolute address. Properties cascade from the
+    /// peripheral defaults only, use `Device::registers` to include the device ones.
+    /// Arrays whose instances do not fit in 32 bits are yielded as errors.
+    pub fn registers(&self) -> RegisterIter<'_> {
+        RegisterIter::new(Some((self, RegisterProperties::default())))
+    }
 }
 
 impl Parse for Peripheral {
@@ -45,47 +122,17 @@ impl Parse for Peripheral {
         if tree.name != "peripheral" {
             return Err(SVDError::NotExpectedTag(tree.clone(), "peripheral".to_string()).into());
         }
-        let name = tree.get_child_text("name")?;
-        Peripheral::_parse(tree, name.clone()).with_context(|| format!("In peripheral `{}`", name))
-    }
-}
 
-impl Peripheral {
-    fn _parse(tree: &Element, name: String) -> Result<Peripheral> {
-        Ok(Peripheral {
-            name,
-            version: tree.get_child_text_opt("version")?,
-            display_name: tree.get_child_text_opt("displayName")?,
-            group_name: tree.get_child_text_opt("groupName")?,
-            description: tree.get_child_text_opt("description")?,
-            base_address: tree.get_child_u32("baseAddress")?,
-            address_block: parse::optional::<AddressBlock>("addressBlock", tree)?,
-            interrupt: {
-                let interrupt: Result<Vec<_>, _> = tree
-                    .children
-                    .iter()
-                    .filter(|t| t.name == "interrupt")
-                    .enumerate()
-                    .map(|(e, i)| {
-                        Interrupt::parse(i).with_context(|| format!("Parsing interrupt #{}", e))
-                    })
-                    .collect();
-                interrupt?
-            },
-            default_register_properties: RegisterProperties::parse(tree)?,
-            registers: if let Some(registers) = tree.get_child("registers") {
-                let rs: Result<Vec<_>, _> = registers
-                    .children
-                    .iter()
-                    .map(RegisterCluster::parse)
-                    .collect();
-                Some(rs?)
-            } else {
-                None
-            },
-            derived_from: tree.attributes.get("derivedFrom").map(|s| s.to_owned()),
-            _extensible: (),
-        })
+        let info = PeripheralInfo::parse(tree)?;
+
+        if tree.get_child("dimIncrement").is_some() {
+            let array_info = DimElement::parse(tree)?;
+            array_info.check(tree, &info.name, info.base_address)?;
+
+            Ok(Peripheral::Array(info, array_info))
+        } else {
+            Ok(Peripheral::Single(info))
+        }
     }
 }
 
@@ -94,69 +141,202 @@ impl Encode for Peripheral {
     type Error = anyhow::Error;
 
     fn encode(&self) -> Result<Element> {
-        let mut elem = Element {
-            prefix: None,
-            namespace: None,
-            namespaces: None,
-            name: String::from("peripheral"),
-            attributes: HashMap::new(),
-            children: vec![new_element("name", Some(self.name.clone()))],
-            text: None,
-        };
+        match self {
+            Peripheral::Single(info) => info.encode(),
+            Peripheral::Array(info, array_info) => {
+                // The dimElementGroup comes first in a peripheral, ahead of its name
+                let mut e = info.encode()?;
+                let mut children = array_info.encode()?.children;
+                children.append(&mut e.children);
+                e.children = children;
+                Ok(e)
+            }
+        }
+    }
+}
 
-        if let Some(v) = &self.version {
-            elem.children
-                .push(new_element("version", Some(format!("{}", v))));
-        };
-        if let Some(v) = &self.display_name {
-            elem.children
-                .push(new_element("displayName", Some(format!("{}", v))));
-        };
-        if let Some(v) = &self.group_name {
-            elem.children
-                .push(new_element("groupName", Some(format!("{}", v))));
-        };
-        if let Some(v) = &self.description {
-            elem.children
-                .push(new_element("description", Some(format!("{}", v))));
-        };
-        elem.children.push(new_element(
-            "baseAddress",
-            Some(format!("0x{:.08x}", self.base_address)),
-        ));
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        elem.children
-            .extend(self.default_register_properties.encode()?);
+    #[test]
+    fn expand() {
+        let tree = Element::parse(
+            "
+            <peripheral>
+                <name>UART[%s]</name>
+                <description>UART %s</description>
+                <baseAddress>0x40004000</baseAddress>
+                <addressBlock>
+                    <offset>0</offset>
+                    <size>0x100</size>
+                    <usage>registers</usage>
+                </addressBlock>
+                <addressBlock>
+                    <offset>0x200</offset>
+                    <size>0x80</size>
+                    <usage>buffer</usage>
+                </addressBlock>
+                <dim>2</dim>
+                <dimIncrement>0x400</dimIncrement>
+            </peripheral>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let peripheral = Peripheral::parse(&tree).unwrap();
+        assert_eq!(peripheral.name, "UART[%s]");
+
+        let peripherals = peripheral.expand().unwrap();
+        let instances: Vec<_> = peripherals
+            .iter()
+            .map(|p| (p.name.as_str(), p.base_address))
+            .collect();
+        assert_eq!(instances, [("UART0", 0x4000_4000), ("UART1", 0x4000_4400)]);
+        assert_eq!(peripherals[1].description, Some(String::from("UART 1")));
+
+        assert_eq!(
+            peripheral.address_ranges().unwrap(),
+            [
+                (String::from("UART0"), 0x4000_4000..0x4000_4100),
+                (String::from("UART0"), 0x4000_4200..0x4000_4280),
+                (String::from("UART1"), 0x4000_4400..0x4000_4500),
+                (String::from("UART1"), 0x4000_4600..0x4000_4680),
+            ]
+        );
+    }
 
-        if let Some(v) = &self.address_block {
-            elem.children.push(v.encode()?);
+    #[test]
+    fn invalid_array() {
+        let parse = |name: &str, base_address: &str, dim: &str| {
+            let xml = format!(
+                "<peripheral>
+                    <name>{}</name>
+                    <baseAddress>{}</baseAddress>
+                    <dim>{}</dim>
+                    <dimIncrement>0x1000</dimIncrement>
+                    <dimIndex>A,B</dimIndex>
+                </peripheral>",
+                name, base_address, dim
+            );
+            let err = Peripheral::parse(&Element::parse(xml.as_bytes()).unwrap()).unwrap_err();
+            err.downcast::<SVDError>().unwrap()
         };
 
-        let interrupts: Result<Vec<_>, _> = self.interrupt.iter().map(Interrupt::encode).collect();
+        assert!(matches!(
+            parse("UART", "0x40004000", "2"),
+            SVDError::MissingDimPlaceholder(_, name) if name == "UART"
+        ));
+        assert!(matches!(
+            parse("UART%s", "0x40004000", "3"),
+            SVDError::DimIndexMismatch(_, name, 3, 2) if name == "UART%s"
+        ));
+        assert_eq!(
+            parse("UART%s", "0xfffff000", "2"),
+            SVDError::DimOverflow(0xffff_f000, 2, 0x1000)
+        );
+    }
 
-        elem.children.append(&mut interrupts?);
+    #[cfg(feature = "unproven")]
+    #[test]
+    fn encode_dim_first() {
+        let tree = Element::parse(
+            "
+            <peripheral>
+                <dim>2</dim>
+                <dimIncrement>0x400</dimIncrement>
+                <name>TIM%s</name>
+                <baseAddress>0x40000000</baseAddress>
+            </peripheral>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let encoded = Peripheral::parse(&tree).unwrap().encode().unwrap();
 
-        if let Some(v) = &self.registers {
-            let children: Result<Vec<_>, _> = v.iter().map(|e| e.encode()).collect();
+        let names: Vec<_> = encoded.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["dim", "dimIncrement", "name", "baseAddress"]);
+    }
 
-            elem.children.push(Element {
-                prefix: None,
-                namespace: None,
-                namespaces: None,
-                name: String::from("registers"),
-                attributes: HashMap::new(),
-                children: children?,
-                text: None,
-            });
-        };
+    #[cfg(feature = "unproven")]
+    #[test]
+    fn decode_encode() {
+        use crate::run_test;
 
-        if let Some(v) = &self.derived_from {
-            elem.attributes
-                .insert(String::from("derivedFrom"), format!("{}", v));
-        }
+        let tests = [
+            (
+                Peripheral::Single(PeripheralInfo {
+                    name: String::from("TIM2"),
+                    version: None,
+                    display_name: None,
+                    group_name: None,
+                    description: None,
+                    alternate_peripheral: Some(String::from("TIM1")),
+                    prepend_to_name: Some(String::from("TIM2_")),
+                    append_to_name: Some(String::from("_R")),
+                    header_struct_name: Some(String::from("TIM")),
+                    disable_condition: Some(String::from("RCC->APB1ENR.TIM2EN == 0")),
+                    base_address: 0x4000_0000,
+                    address_block: Vec::new(),
+                    interrupt: Vec::new(),
+                    default_register_properties: RegisterProperties::default(),
+                    registers: None,
+                    derived_from: None,
+                    _extensible: (),
+                }),
+                "
+            <peripheral>
+                <name>TIM2</name>
+                <alternatePeripheral>TIM1</alternatePeripheral>
+                <prependToName>TIM2_</prependToName>
+                <appendToName>_R</appendToName>
+                <headerStructName>TIM</headerStructName>
+                <disableCondition>RCC->APB1ENR.TIM2EN == 0</disableCondition>
+                <baseAddress>0x40000000</baseAddress>
+            </peripheral>
+            ",
+            ),
+            (
+                Peripheral::Array(
+                    PeripheralInfo {
+                        name: String::from("TIM%s"),
+                        version: None,
+                        display_name: None,
+                        group_name: None,
+                        description: None,
+                        alternate_peripheral: None,
+                        prepend_to_name: None,
+                        append_to_name: None,
+                        header_struct_name: None,
+                        disable_condition: None,
+                        base_address: 0x4000_0000,
+                        address_block: Vec::new(),
+                        interrupt: Vec::new(),
+                        default_register_properties: RegisterProperties::default(),
+                        registers: None,
+                        derived_from: None,
+                        _extensible: (),
+                    },
+                    DimElement {
+                        dim: 2,
+                        dim_increment: 0x400,
+                        dim_index: None,
+                        dim_name: None,
+                        dim_array_index: None,
+                        _extensible: (),
+                    },
+                ),
+                "
+            <peripheral>
+                <name>TIM%s</name>
+                <baseAddress>0x40000000</baseAddress>
+                <dim>2</dim>
+                <dimIncrement>1024</dimIncrement>
+            </peripheral>
+            ",
+            ),
+        ];
 
-        Ok(elem)
+        run_test::<Peripheral>(&tests[..]);
     }
 }
-
-// TODO: add Peripheral encode / decode tests
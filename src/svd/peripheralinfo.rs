@@ -0,0 +1,238 @@
+#[cfg(feature = "unproven")]
+use std::collections::HashMap;
+
+use xmltree::Element;
+
+use crate::elementext::ElementExt;
+
+#[cfg(feature = "unproven")]
+use crate::encode::{Encode, EncodeChildren};
+#[cfg(feature = "unproven")]
+use crate::new_element;
+use crate::types::Parse;
+
+use crate::error::*;
+use crate::svd::{
+    addressblock::AddressBlock, interrupt::Interrupt, registercluster::RegisterCluster,
+    registerproperties::RegisterProperties,
+};
+
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Debug, PartialEq)]
+pub struct PeripheralInfo {
+    pub name: String,
+    pub version: Option<String>,
+    pub display_name: Option<String>,
+    pub group_name: Option<String>,
+    pub description: Option<String>,
+    /// Name of the peripheral sharing the same address space
+    pub alternate_peripheral: Option<String>,
+    /// Prefix of the register names in device headers
+    pub prepend_to_name: Option<String>,
+    /// Suffix of the register names in device headers
+    pub append_to_name: Option<String>,
+    /// Name of the peripheral structure in device headers
+    pub header_struct_name: Option<String>,
+    /// C expression telling when the peripheral must not be accessed
+    pub disable_condition: Option<String>,
+    pub base_address: u32,
+    pub address_block: Vec<AddressBlock>,
+    pub interrupt: Vec<Interrupt>,
+    pub default_register_properties: RegisterProperties,
+    /// `None` indicates that the `<registers>` node is not present
+    pub registers: Option<Vec<RegisterCluster>>,
+    pub derived_from: Option<String>,
+    // Reserve the right to add more fields to this struct
+    pub(crate) _extensible: (),
+}
+
+impl Parse for PeripheralInfo {
+    type Object = PeripheralInfo;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<PeripheralInfo> {
+        let name = tree.get_child_text("name")?;
+        PeripheralInfo::_parse(tree, name.clone())
+            .with_context(|| format!("In peripheral `{}`", name))
+    }
+}
+
+impl PeripheralInfo {
+    /// Name of a register of this peripheral as given in device headers,
+    /// with `prependToName` and `appendToName` applied
+    pub fn header_register_name(&self, name: &str) -> String {
+        format!(
+            "{}{}{}",
+            self.prepend_to_name.as_deref().unwrap_or(""),
+            name,
+            self.append_to_name.as_deref().unwrap_or("")
+        )
+    }
+
+    fn _parse(tree: &Element, name: String) -> Result<PeripheralInfo> {
+        Ok(PeripheralInfo {
+            name,
+            version: tree.get_child_text_opt("version")?,
+            display_name: tree.get_child_text_opt("displayName")?,
+            group_name: tree.get_child_text_opt("groupName")?,
+            description: tree.get_child_text_opt("description")?,
+            alternate_peripheral: tree.get_child_text_opt("alternatePeripheral")?,
+            prepend_to_name: tree.get_child_text_opt("prependToName")?,
+            append_to_name: tree.get_child_text_opt("appendToName")?,
+            header_struct_name: tree.get_child_text_opt("headerStructName")?,
+            disable_condition: tree.get_child_text_opt("disableCondition")?,
+            base_address: tree.get_child_u32("baseAddress")?,
+            address_block: {
+                let blocks: Result<Vec<_>, _> = tree
+                    .children
+                    .iter()
+                    .filter(|t| t.name == "addressBlock")
+                    .map(AddressBlock::parse)
+                    .collect();
+                blocks?
+            },
+            interrupt: {
+                let interrupt: Result<Vec<_>, _> = tree
+                    .children
+                    .iter()
+                    .filter(|t| t.name == "interrupt")
+                    .enumerate()
+                    .map(|(e, i)| {
+                        Interrupt::parse(i).with_context(|| format!("Parsing interrupt #{}", e))
+                    })
+                    .collect();
+                interrupt?
+            },
+            default_register_properties: RegisterProperties::parse(tree)?,
+            registers: if let Some(registers) = tree.get_child("registers") {
+                let rs: Result<Vec<_>, _> = registers
+                    .children
+                    .iter()
+                    .map(RegisterCluster::parse)
+                    .collect();
+                Some(rs?)
+            } else {
+                None
+            },
+            derived_from: tree.attributes.get("derivedFrom").map(|s| s.to_owned()),
+            _extensible: (),
+        })
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Encode for PeripheralInfo {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        let mut elem = Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("peripheral"),
+            attributes: HashMap::new(),
+            children: vec![new_element("name", Some(self.name.clone()))],
+            text: None,
+        };
+
+        if let Some(v) = &self.version {
+            elem.children
+                .push(new_element("version", Some(format!("{}", v))));
+        };
+        if let Some(v) = &self.display_name {
+            elem.children
+                .push(new_element("displayName", Some(format!("{}", v))));
+        };
+        if let Some(v) = &self.group_name {
+            elem.children
+                .push(new_element("groupName", Some(format!("{}", v))));
+        };
+        if let Some(v) = &self.description {
+            elem.children
+                .push(new_element("description", Some(format!("{}", v))));
+        };
+        if let Some(v) = &self.alternate_peripheral {
+            elem.children
+                .push(new_element("alternatePeripheral", Some(v.clone())));
+        };
+        if let Some(v) = &self.prepend_to_name {
+            elem.children
+                .push(new_element("prependToName", Some(v.clone())));
+        };
+        if let Some(v) = &self.append_to_name {
+            elem.children
+                .push(new_element("appendToName", Some(v.clone())));
+        };
+        if let Some(v) = &self.header_struct_name {
+            elem.children
+                .push(new_element("headerStructName", Some(v.clone())));
+        };
+        if let Some(v) = &self.disable_condition {
+            elem.children
+                .push(new_element("disableCondition", Some(v.clone())));
+        };
+        elem.children.push(new_element(
+            "baseAddress",
+            Some(format!("0x{:.08x}", self.base_address)),
+        ));
+
+        elem.children
+            .extend(self.default_register_properties.encode()?);
+
+        let blocks: Result<Vec<_>, _> = self
+            .address_block
+            .iter()
+            .map(AddressBlock::encode)
+            .collect();
+
+        elem.children.append(&mut blocks?);
+
+        let interrupts: Result<Vec<_>, _> = self.interrupt.iter().map(Interrupt::encode).collect();
+
+        elem.children.append(&mut interrupts?);
+
+        if let Some(v) = &self.registers {
+            let children: Result<Vec<_>, _> = v.iter().map(|e| e.encode()).collect();
+
+            elem.children.push(Element {
+                prefix: None,
+                namespace: None,
+                namespaces: None,
+                name: String::from("registers"),
+                attributes: HashMap::new(),
+                children: children?,
+                text: None,
+            });
+        };
+
+        if let Some(v) = &self.derived_from {
+            elem.attributes
+                .insert(String::from("derivedFrom"), format!("{}", v));
+        }
+
+        Ok(elem)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_register_name() {
+        let tree = Element::parse(
+            "
+            <peripheral>
+                <name>TIMER0</name>
+                <baseAddress>0x40008000</baseAddress>
+                <prependToName>TIMER_</prependToName>
+                <appendToName>_REG</appendToName>
+            </peripheral>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let peripheral = PeripheralInfo::parse(&tree).unwrap();
+        assert_eq!(peripheral.header_register_name("CR"), "TIMER_CR_REG");
+    }
+}
@@ -0,0 +1,77 @@
+use xmltree::Element;
+
+use crate::elementext::ElementExt;
+#[cfg(feature = "unproven")]
+use crate::encode::Encode;
+use crate::error::*;
+#[cfg(feature = "unproven")]
+use crate::new_element;
+use crate::types::Parse;
+
+/// Security privilege required to access an address region
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protection {
+    Secure,
+    NonSecure,
+    Privileged,
+}
+
+impl Protection {
+    pub(crate) fn parse_str(s: &str) -> Option<Protection> {
+        match s {
+            "s" => Some(Protection::Secure),
+            "n" => Some(Protection::NonSecure),
+            "p" => Some(Protection::Privileged),
+            _ => None,
+        }
+    }
+
+    #[cfg(feature = "unproven")]
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Protection::Secure => "s",
+            Protection::NonSecure => "n",
+            Protection::Privileged => "p",
+        }
+    }
+}
+
+impl Parse for Protection {
+    type Object = Protection;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<Protection> {
+        let text = tree.get_text()?;
+
+        Protection::parse_str(&text)
+            .ok_or_else(|| SVDError::UnknownProtection(tree.clone(), text).into())
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Encode for Protection {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        Ok(new_element("protection", Some(String::from(self.as_str()))))
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unproven")]
+mod tests {
+    use super::*;
+    use crate::run_test;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [
+            (Protection::Secure, "<protection>s</protection>"),
+            (Protection::NonSecure, "<protection>n</protection>"),
+            (Protection::Privileged, "<protection>p</protection>"),
+        ];
+
+        run_test::<Protection>(&tests[..]);
+    }
+}
@@ -0,0 +1,83 @@
+use crate::elementext::ElementExt;
+#[cfg(feature = "unproven")]
+use std::collections::HashMap;
+use xmltree::Element;
+
+use crate::types::Parse;
+
+#[cfg(feature = "unproven")]
+use crate::encode::Encode;
+use crate::error::*;
+
+/// Side effect of reading a register or field
+#[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReadAction {
+    Clear,
+    Set,
+    Modify,
+    ModifyExternal,
+}
+
+impl Parse for ReadAction {
+    type Object = ReadAction;
+    type Error = anyhow::Error;
+
+    fn parse(tree: &Element) -> Result<ReadAction> {
+        use self::ReadAction::*;
+        let text = tree.get_text()?;
+
+        Ok(match text.as_ref() {
+            "clear" => Clear,
+            "set" => Set,
+            "modify" => Modify,
+            "modifyExternal" => ModifyExternal,
+            s => return Err(SVDError::InvalidReadAction(tree.clone(), s.into()).into()),
+        })
+    }
+}
+
+#[cfg(feature = "unproven")]
+impl Encode for ReadAction {
+    type Error = anyhow::Error;
+
+    fn encode(&self) -> Result<Element> {
+        use self::ReadAction::*;
+        let v = match *self {
+            Clear => "clear",
+            Set => "set",
+            Modify => "modify",
+            ModifyExternal => "modifyExternal",
+        };
+
+        Ok(Element {
+            prefix: None,
+            namespace: None,
+            namespaces: None,
+            name: String::from("readAction"),
+            attributes: HashMap::new(),
+            children: vec![],
+            text: Some(v.into()),
+        })
+    }
+}
+
+#[cfg(test)]
+#[cfg(feature = "unproven")]
+mod tests {
+    use super::*;
+    use crate::run_test;
+
+    #[test]
+    fn decode_encode() {
+        let tests = [
+            (ReadAction::Clear, "<readAction>clear</readAction>"),
+            (
+                ReadAction::ModifyExternal,
+                "<readAction>modifyExternal</readAction>",
+            ),
+        ];
+
+        run_test::<ReadAction>(&tests[..]);
+    }
+}
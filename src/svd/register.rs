@@ -1,4 +1,4 @@
-use core::ops::Deref;
+use core::ops::{Deref, DerefMut};
 
 use xmltree::Element;
 
@@ -8,7 +8,10 @@ use crate::types::Parse;
 use crate::elementext::ElementExt;
 #[cfg(feature = "unproven")]
 use crate::encode::Encode;
-use crate::svd::{dimelement::DimElement, registerinfo::RegisterInfo};
+use crate::svd::{
+    dimelement::{replace_index, DimElement},
+    registerinfo::RegisterInfo,
+};
 use anyhow::Result;
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
@@ -29,6 +32,43 @@ impl Deref for Register {
     }
 }
 
+impl DerefMut for Register {
+    fn deref_mut(&mut self) -> &mut RegisterInfo {
+        match self {
+            Register::Single(info) => info,
+            Register::Array(info, _) => info,
+        }
+    }
+}
+
+impl Register {
+    /// Returns the concrete registers described by this element
+    ///
+    /// For arrays, `%s` is replaced by each index in the name, display name and description and the
+    /// address offset advances by `dimIncrement` per instance. Fails when an offset does not
+    /// fit in 32 bits.
+    pub fn expand(&self) -> Result<Vec<RegisterInfo>> {
+        Ok(match self {
+            Register::Single(info) => vec![info.clone()],
+            Register::Array(info, array_info) => array_info
+                .indexes()
+                .iter()
+                .zip(array_info.offsets(info.address_offset)?)
+                .map(|(index, address_offset)| {
+                    let mut register = info.clone();
+                    register.name = replace_index(&info.name, index);
+                    register.display_name =
+                        info.display_name.as_ref().map(|d| replace_index(d, index));
+                    register.description =
+                        info.description.as_ref().map(|d| replace_index(d, index));
+                    register.address_offset = address_offset;
+                    register
+                })
+                .collect(),
+        })
+    }
+}
+
 impl Parse for Register {
     type Object = Register;
     type Error = anyhow::Error;
@@ -40,10 +80,7 @@ impl Parse for Register {
 
         if tree.get_child("dimIncrement").is_some() {
             let array_info = DimElement::parse(tree)?;
-            assert!(info.name.contains("%s"));
-            if let Some(indices) = &array_info.dim_index {
-                assert_eq!(array_info.dim as usize, indices.len())
-            }
+            array_info.check(tree, &info.name, info.address_offset)?;
             Ok(Register::Array(info, array_info))
         } else {
             Ok(Register::Single(info))
@@ -59,13 +96,140 @@ impl Encode for Register {
         match self {
             Register::Single(info) => info.encode(),
             Register::Array(info, array_info) => {
-                // TODO: is this correct? probably not, need tests
-                let base = info.encode()?;
-                base.merge(&array_info.encode()?);
-                Ok(base)
+                let mut e = info.encode()?;
+                e = e.merge(&array_info.encode()?);
+                Ok(e)
             }
         }
     }
 }
 
-// TODO: add Register encode and decode tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expand() {
+        let tree = Element::parse(
+            "
+            <register>
+                <name>CCR[%s]</name>
+                <description>Channel %s control</description>
+                <addressOffset>0x10</addressOffset>
+                <dim>3</dim>
+                <dimIncrement>4</dimIncrement>
+                <dimIndex>A,B,C</dimIndex>
+            </register>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let registers = Register::parse(&tree).unwrap().expand().unwrap();
+
+        let names: Vec<_> = registers.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["CCRA", "CCRB", "CCRC"]);
+        let offsets: Vec<_> = registers.iter().map(|r| r.address_offset).collect();
+        assert_eq!(offsets, [0x10, 0x14, 0x18]);
+        assert_eq!(
+            registers[2].description,
+            Some(String::from("Channel C control"))
+        );
+    }
+
+    #[test]
+    fn invalid_array() {
+        use crate::error::SVDError;
+
+        let parse = |name: &str, dim: &str| {
+            let xml = format!(
+                "<register>
+                    <name>{}</name>
+                    <addressOffset>0x10</addressOffset>
+                    <dim>{}</dim>
+                    <dimIncrement>4</dimIncrement>
+                    <dimIndex>A,B</dimIndex>
+                </register>",
+                name, dim
+            );
+            let err = Register::parse(&Element::parse(xml.as_bytes()).unwrap()).unwrap_err();
+            err.downcast::<SVDError>().unwrap()
+        };
+
+        assert!(matches!(
+            parse("CCR", "2"),
+            SVDError::MissingDimPlaceholder(_, name) if name == "CCR"
+        ));
+        assert!(matches!(
+            parse("CCR%s", "3"),
+            SVDError::DimIndexMismatch(_, name, 3, 2) if name == "CCR%s"
+        ));
+    }
+
+    #[test]
+    fn offset_overflow() {
+        let tree = Element::parse(
+            "
+            <register>
+                <name>BUF%s</name>
+                <addressOffset>0x10</addressOffset>
+                <dim>0x10001</dim>
+                <dimIncrement>0x10000</dimIncrement>
+            </register>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        assert!(Register::parse(&tree).is_err());
+
+        let array_info = DimElement {
+            dim: 0x10001,
+            dim_increment: 0x10000,
+            dim_index: None,
+            dim_name: None,
+            dim_array_index: None,
+            _extensible: (),
+        };
+        let info = RegisterInfo::parse(&tree).unwrap();
+        assert!(Register::Array(info, array_info).expand().is_err());
+    }
+
+    #[cfg(feature = "unproven")]
+    #[test]
+    fn decode_encode() {
+        use crate::run_test;
+
+        let tree = Element::parse(
+            "
+            <register>
+                <name>CLK%s</name>
+                <addressOffset>0x0</addressOffset>
+                <dim>2</dim>
+                <dimIncrement>4</dimIncrement>
+                <dimName>CLK_TypeDef</dimName>
+            </register>
+            "
+            .as_bytes(),
+        )
+        .unwrap();
+        let register = Register::parse(&tree).unwrap();
+        match &register {
+            Register::Array(_, array_info) => {
+                assert_eq!(array_info.dim_name, Some(String::from("CLK_TypeDef")))
+            }
+            Register::Single(_) => panic!("expected a register array"),
+        }
+
+        run_test::<Register>(&[(
+            register,
+            "
+            <register>
+                <name>CLK%s</name>
+                <addressOffset>0x0</addressOffset>
+                <dim>2</dim>
+                <dimIncrement>4</dimIncrement>
+                <dimName>CLK_TypeDef</dimName>
+            </register>
+            ",
+        )]);
+    }
+}
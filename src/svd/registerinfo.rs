@@ -13,27 +13,32 @@ use crate::parse;
 use crate::types::Parse;
 
 use crate::svd::{
-    access::Access, field::Field, modifiedwritevalues::ModifiedWriteValues,
-    registerproperties::RegisterProperties, writeconstraint::WriteConstraint,
+    access::Access, datatype::DataType, field::Field, modifiedwritevalues::ModifiedWriteValues,
+    protection::Protection, readaction::ReadAction, registerproperties::RegisterProperties,
+    writeconstraint::WriteConstraint,
 };
 
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
 #[derive(Clone, Debug, PartialEq)]
 pub struct RegisterInfo {
     pub name: String,
+    pub display_name: Option<String>,
     pub alternate_group: Option<String>,
     pub alternate_register: Option<String>,
     pub derived_from: Option<String>,
     pub description: Option<String>,
     pub address_offset: u32,
     pub size: Option<u32>,
+    pub data_type: Option<DataType>,
     pub access: Option<Access>,
+    pub protection: Option<Protection>,
     pub reset_value: Option<u32>,
     pub reset_mask: Option<u32>,
     /// `None` indicates that the `<fields>` node is not present
     pub fields: Option<Vec<Field>>,
     pub write_constraint: Option<WriteConstraint>,
     pub modified_write_values: Option<ModifiedWriteValues>,
+    pub read_action: Option<ReadAction>,
     // Reserve the right to add more fields to this struct
     pub(crate) _extensible: (),
 }
@@ -49,17 +54,32 @@ impl Parse for RegisterInfo {
 }
 
 impl RegisterInfo {
+    /// Register properties set on this register itself
+    pub fn properties(&self) -> RegisterProperties {
+        RegisterProperties {
+            size: self.size,
+            reset_value: self.reset_value,
+            reset_mask: self.reset_mask,
+            access: self.access,
+            protection: self.protection,
+            _extensible: (),
+        }
+    }
+
     fn _parse(tree: &Element, name: String) -> Result<RegisterInfo> {
         let properties = RegisterProperties::parse(tree)?;
         Ok(RegisterInfo {
             name,
+            display_name: tree.get_child_text_opt("displayName")?,
             alternate_group: tree.get_child_text_opt("alternateGroup")?,
             alternate_register: tree.get_child_text_opt("alternateRegister")?,
             description: tree.get_child_text_opt("description")?,
             derived_from: tree.attributes.get("derivedFrom").map(|s| s.to_owned()),
             address_offset: tree.get_child_u32("addressOffset")?,
             size: properties.size,
+            data_type: parse::optional::<DataType>("dataType", tree)?,
             access: properties.access,
+            protection: properties.protection,
             reset_value: properties.reset_value,
             reset_mask: properties.reset_mask,
             fields: {
@@ -82,6 +102,7 @@ impl RegisterInfo {
                 "modifiedWriteValues",
                 tree,
             )?,
+            read_action: parse::optional::<ReadAction>("readAction", tree)?,
             _extensible: (),
         })
     }
@@ -107,6 +128,10 @@ impl Encode for RegisterInfo {
             ],
             text: None,
         };
+        if let Some(v) = &self.display_name {
+            elem.children
+                .push(new_element("displayName", Some(v.clone())));
+        }
         if let Some(v) = &self.description {
             elem.children
                 .push(new_element("description", Some(v.clone())));
@@ -131,10 +156,18 @@ impl Encode for RegisterInfo {
                 .push(new_element("size", Some(format!("{}", v))));
         };
 
+        if let Some(v) = &self.data_type {
+            elem.children.push(v.encode()?);
+        };
+
         if let Some(v) = &self.access {
             elem.children.push(v.encode()?);
         };
 
+        if let Some(v) = &self.protection {
+            elem.children.push(v.encode()?);
+        };
+
         if let Some(v) = &self.reset_value {
             elem.children
                 .push(new_element("resetValue", Some(format!("0x{:08.x}", v))));
@@ -172,6 +205,10 @@ impl Encode for RegisterInfo {
             elem.children.push(v.encode()?);
         };
 
+        if let Some(v) = &self.read_action {
+            elem.children.push(v.encode()?);
+        };
+
         Ok(elem)
     }
 }
@@ -189,13 +226,16 @@ mod tests {
         let tests = vec![(
             RegisterInfo {
                 name: String::from("WRITECTRL"),
+                display_name: Some(String::from("Write Control")),
                 alternate_group: Some(String::from("alternate group")),
                 alternate_register: Some(String::from("alternate register")),
                 derived_from: Some(String::from("derived from")),
                 description: Some(String::from("Write Control Register")),
                 address_offset: 8,
                 size: Some(32),
+                data_type: Some(DataType::U32),
                 access: Some(Access::ReadWrite),
+                protection: Some(Protection::NonSecure),
                 reset_value: Some(0x00000000),
                 reset_mask: Some(0x00000023),
                 fields: Some(vec![Field::Single(FieldInfo {
@@ -211,21 +251,26 @@ mod tests {
                     enumerated_values: Vec::new(),
                     write_constraint: None,
                     modified_write_values: None,
+                    read_action: None,
                     _extensible: (),
                 })]),
                 write_constraint: None,
                 modified_write_values: Some(ModifiedWriteValues::OneToToggle),
+                read_action: Some(ReadAction::Clear),
                 _extensible: (),
             },
             "
             <register derivedFrom=\"derived from\">
                 <name>WRITECTRL</name>
+                <displayName>Write Control</displayName>
                 <description>Write Control Register</description>
                 <addressOffset>0x8</addressOffset>
                 <alternateGroup>alternate group</alternateGroup>
                 <alternateRegister>alternate register</alternateRegister>
                 <size>32</size>
+                <dataType>uint32_t</dataType>
                 <access>read-write</access>
+                <protection>n</protection>
                 <resetValue>0x00000000</resetValue>
                 <resetMask>0x00000023</resetMask>
                 <fields>
@@ -238,6 +283,7 @@ mod tests {
                     </field>
                 </fields>
                 <modifiedWriteValues>oneToToggle</modifiedWriteValues>
+                <readAction>clear</readAction>
             </register>
             ",
         )];
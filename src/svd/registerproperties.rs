@@ -10,18 +10,33 @@ use crate::new_element;
 use crate::parse;
 use crate::types::Parse;
 
-use crate::svd::access::Access;
+use crate::svd::{access::Access, protection::Protection};
 
 /// Register default properties
 #[cfg_attr(feature = "serde", derive(serde::Deserialize, serde::Serialize))]
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub struct RegisterProperties {
     pub size: Option<u32>,
     pub reset_value: Option<u32>,
     pub reset_mask: Option<u32>,
     pub access: Option<Access>,
+    pub protection: Option<Protection>,
     // Reserve the right to add more fields to this struct
-    _extensible: (),
+    pub(crate) _extensible: (),
+}
+
+impl RegisterProperties {
+    /// Fill properties that are not set with the ones of an enclosing element
+    pub fn inherit(&self, parent: &Self) -> Self {
+        RegisterProperties {
+            size: self.size.or(parent.size),
+            reset_value: self.reset_value.or(parent.reset_value),
+            reset_mask: self.reset_mask.or(parent.reset_mask),
+            access: self.access.or(parent.access),
+            protection: self.protection.or(parent.protection),
+            _extensible: (),
+        }
+    }
 }
 
 impl Parse for RegisterProperties {
@@ -34,6 +49,7 @@ impl Parse for RegisterProperties {
             reset_value: parse::optional::<u32>("resetValue", tree)?,
             reset_mask: parse::optional::<u32>("resetMask", tree)?,
             access: parse::optional::<Access>("access", tree)?,
+            protection: parse::optional::<Protection>("protection", tree)?,
             _extensible: (),
         })
     }
@@ -62,6 +78,10 @@ impl EncodeChildren for RegisterProperties {
             children.push(v.encode()?);
         };
 
+        if let Some(v) = &self.protection {
+            children.push(v.encode()?);
+        };
+
         Ok(children)
     }
 }
@@ -80,6 +100,7 @@ mod tests {
                 <resetValue>0x11223344</resetValue>
                 <resetMask>0x00000000</resetMask>
                 <access>read-only</access>
+                <protection>s</protection>
             </mock>
         ",
         );
@@ -89,6 +110,7 @@ mod tests {
             reset_value: Some(0x11223344),
             reset_mask: Some(0x00000000),
             access: Some(Access::ReadOnly),
+            protection: Some(Protection::Secure),
             _extensible: (),
         };
 
@@ -28,7 +28,7 @@ impl Parse for u32 {
         } else if text.starts_with('#') {
             // Handle strings in the binary form of:
             // #01101x1
-            // along with don't care character x (replaced with 0)
+            // along with don't care character x (replaced with 0, `BitPattern` keeps them)
             u32::from_str_radix(
                 &str::replace(&text.to_lowercase()["#".len()..], "x", "0"),
                 2,
@@ -37,7 +37,7 @@ impl Parse for u32 {
         } else if text.starts_with("0b") {
             // Handle strings in the binary form of:
             // 0b01101x1
-            // along with don't care character x (replaced with 0)
+            // along with don't care character x (replaced with 0, `BitPattern` keeps them)
             u32::from_str_radix(&str::replace(&text["0b".len()..], "x", "0"), 2)
                 .context(format!("{} invalid", text))
         } else {
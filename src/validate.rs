@@ -0,0 +1,1327 @@
+//! Device validation.
+//! Checks of the consistency of a device that go beyond what parsing enforces
+
+use core::fmt;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use crate::iter::{RegisterItem, RegisterIter};
+use crate::svd::{
+    bitpattern::BitPattern, bitrange::BitRange, device::Device, enumeratedvalues::EnumeratedValues,
+    fieldinfo::FieldInfo, interrupt::Interrupt, register::Register,
+    registercluster::RegisterCluster, registerproperties::RegisterProperties, usage::Usage,
+    writeconstraint::WriteConstraint,
+};
+
+/// Severity of a diagnostic
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Warning,
+    Error,
+}
+
+/// An issue found in a device
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub level: Level,
+    /// Dotted path of the offending element, such as `PERIPH.REG`
+    pub path: String,
+    pub issue: Issue,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.path, self.issue)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum Issue {
+    #[error("Overlaps register `{0}`")]
+    RegisterOverlap(String),
+    #[error("Overlaps peripheral `{0}`")]
+    PeripheralOverlap(String),
+    #[error("Lies outside every address block of its peripheral")]
+    OutsideAddressBlocks,
+    #[error("Bits [{0}:{1}] exceed the {2}-bit register")]
+    FieldOutOfRange(u64, u32, u32),
+    #[error("Overlaps field `{0}`")]
+    FieldOverlap(String),
+    #[error("Zero-width field")]
+    ZeroWidthField,
+    #[error("Duplicate name")]
+    DuplicateName,
+    #[error("Value {0:#x} does not fit in {1} bits")]
+    ValueOutOfRange(u32, u32),
+    #[error("Same value as `{0}`")]
+    DuplicateValue(String),
+    #[error("Header enum name `{0}` is also used by `{1}`")]
+    DuplicateHeaderEnumName(String, String),
+    #[error("More than one default value")]
+    MultipleDefaults,
+    #[error("More than one set of enumerated values for reading")]
+    MultipleReadSets,
+    #[error("More than one set of enumerated values for writing")]
+    MultipleWriteSets,
+    #[error("Covers {0} of {1} possible values and has no default")]
+    IncompleteEnumeration(u64, u64),
+    #[error("Reset value {0:#x} does not fit in {1} bits")]
+    ResetValueOutOfRange(u32, u32),
+    #[error("Reset mask {0:#x} does not fit in {1} bits")]
+    ResetMaskOutOfRange(u32, u32),
+    #[error("Reset value {0:#x} has bits set outside reset mask {1:#x}")]
+    ResetValueOutsideMask(u32, u32),
+    #[error("Reset value {0:#x} is not one of the enumerated values")]
+    ResetValueNotEnumerated(u32),
+    #[error("Reset value {0:#x} is outside the write range {1:#x}..={2:#x}")]
+    ResetValueOutsideRange(u32, u32, u32),
+    #[error("Also declared with value {0}")]
+    ConflictingValue(u32),
+    #[error("Value {0} exceeds the {1} interrupts of the device")]
+    InterruptOutOfRange(u32, u32),
+    #[error("Base {0:#x} is above limit {1:#x}")]
+    SauRegionInverted(u32, u32),
+    #[error("Base {0:#x} or limit {1:#x} is not aligned on 32 bytes")]
+    SauRegionMisaligned(u32, u32),
+    #[error("{0} regions exceed the {1} regions of the SAU")]
+    TooManySauRegions(usize, u32),
+    #[error("{0}")]
+    ArrayOverflow(String),
+}
+
+/// Runs every check on a device
+pub fn device(device: &Device) -> Vec<Diagnostic> {
+    let mut diagnostics = memory_map(device);
+    diagnostics.extend(fields(device));
+    diagnostics.extend(enumerated_values(device));
+    diagnostics.extend(reset_values(device));
+    diagnostics.extend(interrupts(device));
+    diagnostics.extend(sau(device));
+    diagnostics
+}
+
+/// Checks the address space of a device
+///
+/// Reports registers overlapping each other unless explained by `alternateRegister` or by
+/// being in different `alternateGroup`s, peripherals whose address blocks overlap, and
+/// registers lying outside the address blocks of their peripheral, reserved blocks not
+/// counting. Blocks of the same peripheral may overlap, as may peripherals declared as
+/// `alternatePeripheral` of each other. Arrays whose instances do not fit in 32-bit
+/// addresses or offsets are reported as well.
+pub fn memory_map(device: &Device) -> Vec<Diagnostic> {
+    let unit_bits = device.address_unit_bits.unwrap_or(8);
+    let mut diagnostics = Vec::new();
+
+    let mut blocks: Vec<(Range<u64>, String, Option<&str>)> = Vec::new();
+    for p in &device.peripherals {
+        let mut registers = Vec::new();
+        for r in RegisterIter::new(Some((p, device.default_register_properties))) {
+            match r {
+                Ok(r) => registers.push(r),
+                Err(e) => diagnostics.push(array_overflow(p.name.clone(), &e)),
+            }
+        }
+        registers.sort_by_key(|r| r.address);
+
+        for (i, r) in registers.iter().enumerate() {
+            let span = r.span(unit_bits);
+            for other in registers[i + 1..]
+                .iter()
+                .take_while(|o| o.address < span.end)
+            {
+                if !is_alternate(r, other) {
+                    diagnostics.push(Diagnostic {
+                        level: Level::Error,
+                        path: other.name(),
+                        issue: Issue::RegisterOverlap(r.name()),
+                    });
+                }
+            }
+        }
+
+        // Peripheral arrays running past 32-bit addresses are reported by the iterator above
+        let ranges = p.address_ranges().unwrap_or_default();
+        if !p.address_block.is_empty() {
+            for r in &registers {
+                let span = r.span(unit_bits);
+                if !ranges.iter().any(|(name, block)| {
+                    *name == r.path[0] && block.start <= span.start && span.end <= block.end
+                }) {
+                    diagnostics.push(Diagnostic {
+                        level: Level::Error,
+                        path: r.name(),
+                        issue: Issue::OutsideAddressBlocks,
+                    });
+                }
+            }
+        }
+        let alternate = p.alternate_peripheral.as_deref();
+        blocks.extend(
+            ranges
+                .into_iter()
+                .map(|(name, block)| (block, name, alternate)),
+        );
+    }
+
+    blocks.sort_by_key(|(b, _, _)| b.start);
+    for (i, (block, name, alternate)) in blocks.iter().enumerate() {
+        for (_, other, _) in blocks[i + 1..]
+            .iter()
+            .take_while(|(o, _, _)| o.start < block.end)
+            .filter(|(_, other, other_alternate)| {
+                other != name
+                    && *alternate != Some(other.as_str())
+                    && *other_alternate != Some(name.as_str())
+            })
+        {
+            diagnostics.push(Diagnostic {
+                level: Level::Error,
+                path: other.clone(),
+                issue: Issue::PeripheralOverlap(name.clone()),
+            });
+        }
+    }
+
+    diagnostics
+}
+
+/// Checks the layout of the fields of every register
+///
+/// Reports fields exceeding the effective size of their register (32 bits when unknown),
+/// fields overlapping each other, zero-width fields, duplicate field names and field arrays
+/// whose bit offsets do not fit in 32 bits.
+pub fn fields(device: &Device) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for_each_register(device, &mut |path, properties, register| {
+        let size = properties.size.unwrap_or(32);
+        let mut fields = expand_fields(&path, register, &mut diagnostics);
+
+        let mut names = HashSet::new();
+        for f in &fields {
+            let field_path = format!("{}.{}", path, f.name);
+            if !names.insert(&f.name) {
+                diagnostics.push(Diagnostic {
+                    level: Level::Error,
+                    path: field_path.clone(),
+                    issue: Issue::DuplicateName,
+                });
+            }
+            if f.bit_range.width == 0 {
+                diagnostics.push(Diagnostic {
+                    level: Level::Error,
+                    path: field_path,
+                    issue: Issue::ZeroWidthField,
+                });
+            } else if msb(&f.bit_range) >= u64::from(size) {
+                diagnostics.push(Diagnostic {
+                    level: Level::Error,
+                    path: field_path,
+                    issue: Issue::FieldOutOfRange(msb(&f.bit_range), f.bit_range.lsb(), size),
+                });
+            }
+        }
+
+        fields.retain(|f| f.bit_range.width > 0);
+        fields.sort_by_key(|f| f.bit_range.lsb());
+        for (i, f) in fields.iter().enumerate() {
+            for other in fields[i + 1..]
+                .iter()
+                .take_while(|o| u64::from(o.bit_range.lsb()) <= msb(&f.bit_range))
+            {
+                diagnostics.push(Diagnostic {
+                    level: Level::Error,
+                    path: format!("{}.{}", path, other.name),
+                    issue: Issue::FieldOverlap(f.name.clone()),
+                });
+            }
+        }
+    });
+    diagnostics
+}
+
+/// Checks the enumerated values of every field
+///
+/// Reports values not fitting in the field, duplicate names and values within a set, values
+/// with don't-care bits being duplicates when they match a common raw value,
+/// several default entries in a set, and more than one set used for reading or for writing
+/// in a field. Sets neither covering every possible value nor having a default get a
+/// warning. Sets without values, that are only `derivedFrom` another one, are skipped.
+///
+/// `headerEnumName`s must also be unique across the device, sets `derivedFrom` another one
+/// may reuse its name.
+pub fn enumerated_values(device: &Device) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut header_enum_names: HashMap<&str, String> = HashMap::new();
+    for_each_register(device, &mut |path, _, register| {
+        for f in register.fields.iter().flatten() {
+            let path = format!("{}.{}", path, f.name);
+            check_enumerated_values(&path, f, &mut diagnostics);
+            for set in &f.enumerated_values {
+                let name = match &set.header_enum_name {
+                    Some(name) if set.derived_from.is_none() => name,
+                    _ => continue,
+                };
+                match header_enum_names.get(name.as_str()) {
+                    Some(other) => diagnostics.push(Diagnostic {
+                        level: Level::Error,
+                        path: path.clone(),
+                        issue: Issue::DuplicateHeaderEnumName(name.clone(), other.clone()),
+                    }),
+                    None => {
+                        header_enum_names.insert(name, path.clone());
+                    }
+                }
+            }
+        }
+    });
+    diagnostics
+}
+
+fn check_enumerated_values(path: &str, field: &FieldInfo, diagnostics: &mut Vec<Diagnostic>) {
+    let width = field.bit_range.width;
+    let (mut read_sets, mut write_sets) = (0, 0);
+    for set in &field.enumerated_values {
+        if set.values.is_empty() {
+            continue;
+        }
+        let set_path = match &set.name {
+            Some(name) => format!("{}.{}", path, name),
+            None => path.to_string(),
+        };
+        match set.usage {
+            Some(Usage::Read) => read_sets += 1,
+            Some(Usage::Write) => write_sets += 1,
+            Some(Usage::ReadWrite) | None => {
+                read_sets += 1;
+                write_sets += 1;
+            }
+        }
+        check_enumerated_value_set(&set_path, width, set, diagnostics);
+    }
+    if read_sets > 1 {
+        diagnostics.push(Diagnostic {
+            level: Level::Error,
+            path: path.to_string(),
+            issue: Issue::MultipleReadSets,
+        });
+    }
+    if write_sets > 1 {
+        diagnostics.push(Diagnostic {
+            level: Level::Error,
+            path: path.to_string(),
+            issue: Issue::MultipleWriteSets,
+        });
+    }
+}
+
+fn check_enumerated_value_set(
+    path: &str,
+    width: u32,
+    set: &EnumeratedValues,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let mut names = HashSet::new();
+    let mut values: Vec<(BitPattern, &String)> = Vec::new();
+    let mut defaults = 0;
+    for v in &set.values {
+        let value_path = format!("{}.{}", path, v.name);
+        if !names.insert(&v.name) {
+            diagnostics.push(Diagnostic {
+                level: Level::Error,
+                path: value_path.clone(),
+                issue: Issue::DuplicateName,
+            });
+        }
+        if v.is_default == Some(true) {
+            defaults += 1;
+        }
+        if let Some(value) = v.value {
+            if width < 32 && value.value >> width != 0 {
+                diagnostics.push(Diagnostic {
+                    level: Level::Error,
+                    path: value_path.clone(),
+                    issue: Issue::ValueOutOfRange(value.value, width),
+                });
+            }
+            match values.iter().find(|(other, _)| other.overlaps(&value)) {
+                Some((_, other)) => diagnostics.push(Diagnostic {
+                    level: Level::Error,
+                    path: value_path,
+                    issue: Issue::DuplicateValue(other.to_string()),
+                }),
+                None => values.push((value, &v.name)),
+            }
+        }
+    }
+
+    if defaults > 1 {
+        diagnostics.push(Diagnostic {
+            level: Level::Error,
+            path: path.to_string(),
+            issue: Issue::MultipleDefaults,
+        });
+    }
+    if defaults == 0 && width < 64 {
+        let possible = 1u64 << width;
+        let covered = values.iter().map(|(v, _)| v.count(width)).sum();
+        if covered < possible {
+            diagnostics.push(Diagnostic {
+                level: Level::Warning,
+                path: path.to_string(),
+                issue: Issue::IncompleteEnumeration(covered, possible),
+            });
+        }
+    }
+}
+
+/// Checks the reset value and reset mask of every register
+///
+/// Reports reset values and masks set on the register itself not fitting in its effective
+/// size (32 bits when unknown), and reset values with bits set outside the reset mask.
+/// Inherited values and masks may be wider than a smaller register, inherited values are
+/// truncated to its size.
+/// Reset values of registers and of fields whose bits are all covered by the reset mask
+/// get a warning when breaking their write constraint: outside of a `range`, or not one
+/// of the enumerated values with `useEnumeratedValues`.
+pub fn reset_values(device: &Device) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for_each_register(device, &mut |path, properties, register| {
+        let size = properties.size.unwrap_or(32);
+        let size_mask = if size >= 32 {
+            u32::MAX
+        } else {
+            (1 << size) - 1
+        };
+        let fits = |value: u32| value & !size_mask == 0;
+        if let Some(mask) = register.reset_mask {
+            if !fits(mask) {
+                diagnostics.push(Diagnostic {
+                    level: Level::Error,
+                    path: path.clone(),
+                    issue: Issue::ResetMaskOutOfRange(mask, size),
+                });
+            }
+        }
+        let value = match (register.reset_value, properties.reset_value) {
+            (Some(value), _) => {
+                if !fits(value) {
+                    diagnostics.push(Diagnostic {
+                        level: Level::Error,
+                        path: path.clone(),
+                        issue: Issue::ResetValueOutOfRange(value, size),
+                    });
+                }
+                value
+            }
+            (None, Some(value)) => value & size_mask,
+            (None, None) => return,
+        };
+        let mask = properties.reset_mask.unwrap_or(u32::MAX);
+        if value & !mask != 0 {
+            diagnostics.push(Diagnostic {
+                level: Level::Error,
+                path: path.clone(),
+                issue: Issue::ResetValueOutsideMask(value, mask),
+            });
+        }
+        if let Some(constraint) = &register.write_constraint {
+            check_reset_constraint(&path, value, constraint, &[], &mut diagnostics);
+        }
+
+        for f in expand_fields(&path, register, &mut diagnostics) {
+            let (lsb, width) = (f.bit_range.lsb(), f.bit_range.width);
+            if width == 0 || lsb >= 32 {
+                continue;
+            }
+            let field_mask = if width >= 32 {
+                u32::MAX
+            } else {
+                (1 << width) - 1
+            };
+            if (mask >> lsb) & field_mask != field_mask {
+                continue;
+            }
+            if let Some(constraint) = &f.write_constraint {
+                check_reset_constraint(
+                    &format!("{}.{}", path, f.name),
+                    (value >> lsb) & field_mask,
+                    constraint,
+                    &f.enumerated_values,
+                    &mut diagnostics,
+                );
+            }
+        }
+    });
+    diagnostics
+}
+
+fn check_reset_constraint(
+    path: &str,
+    value: u32,
+    constraint: &WriteConstraint,
+    enumerated_values: &[EnumeratedValues],
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    match constraint {
+        WriteConstraint::Range(range) if value < range.min || value > range.max => diagnostics
+            .push(Diagnostic {
+                level: Level::Warning,
+                path: path.to_string(),
+                issue: Issue::ResetValueOutsideRange(value, range.min, range.max),
+            }),
+        WriteConstraint::UseEnumeratedValues(true) => {
+            let values: Vec<_> = enumerated_values
+                .iter()
+                .filter(|set| set.usage != Some(Usage::Read))
+                .flat_map(|set| &set.values)
+                .collect();
+            // Sets only derived from another one can't be checked
+            if !values.is_empty()
+                && !values.iter().any(|v| {
+                    v.value.is_some_and(|p| p.matches(value)) || v.is_default == Some(true)
+                })
+            {
+                diagnostics.push(Diagnostic {
+                    level: Level::Warning,
+                    path: path.to_string(),
+                    issue: Issue::ResetValueNotEnumerated(value),
+                });
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Checks the interrupts of a device
+///
+/// Reports interrupts declared with different values under the same name, different
+/// interrupts sharing a value, and values not below the `deviceNumInterrupts` of the CPU
+/// when known. Interrupts inherited by `derivedFrom` peripherals are
+/// included, see `Device::interrupts`.
+pub fn interrupts(device: &Device) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut names: Vec<&Interrupt> = Vec::new();
+    let mut values: Vec<&Interrupt> = Vec::new();
+    let count = device
+        .cpu
+        .as_ref()
+        .and_then(|cpu| cpu.device_num_interrupts);
+    for (p, interrupt) in device.peripheral_interrupts() {
+        let path = format!("{}.{}", p.name, interrupt.name);
+        match names.iter().find(|i| i.name == interrupt.name) {
+            Some(other) if other.value != interrupt.value => diagnostics.push(Diagnostic {
+                level: Level::Error,
+                path: path.clone(),
+                issue: Issue::ConflictingValue(other.value),
+            }),
+            Some(_) => continue,
+            None => names.push(interrupt),
+        }
+        match count {
+            Some(count) if interrupt.value >= count => diagnostics.push(Diagnostic {
+                level: Level::Error,
+                path: path.clone(),
+                issue: Issue::InterruptOutOfRange(interrupt.value, count),
+            }),
+            _ => {}
+        }
+        match values.iter().find(|i| i.value == interrupt.value) {
+            Some(other) => diagnostics.push(Diagnostic {
+                level: Level::Error,
+                path,
+                issue: Issue::DuplicateValue(other.name.clone()),
+            }),
+            None => values.push(interrupt),
+        }
+    }
+    diagnostics
+}
+
+/// Checks the SAU regions configuration of the CPU
+///
+/// Reports regions whose base is above their limit, bases not aligned on 32 bytes, limits
+/// neither aligned on 32 bytes nor ending a 32-byte block, and more regions than the
+/// `sauNumRegions` of the CPU when known. Regions are named by their `name` attribute or
+/// their position.
+pub fn sau(device: &Device) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let cpu = match &device.cpu {
+        Some(cpu) => cpu,
+        None => return diagnostics,
+    };
+    let config = match &cpu.sau_regions_config {
+        Some(config) => config,
+        None => return diagnostics,
+    };
+    let path = format!("{}.sauRegionsConfig", cpu.name);
+
+    for (i, region) in config.regions.iter().enumerate() {
+        let region_path = match &region.name {
+            Some(name) => format!("{}.{}", path, name),
+            None => format!("{}.{}", path, i),
+        };
+        if region.base > region.limit {
+            diagnostics.push(Diagnostic {
+                level: Level::Error,
+                path: region_path.clone(),
+                issue: Issue::SauRegionInverted(region.base, region.limit),
+            });
+        }
+        if region.base % 32 != 0 || (region.limit % 32 != 0 && region.limit % 32 != 31) {
+            diagnostics.push(Diagnostic {
+                level: Level::Error,
+                path: region_path,
+                issue: Issue::SauRegionMisaligned(region.base, region.limit),
+            });
+        }
+    }
+    match cpu.sau_num_regions {
+        Some(count) if config.regions.len() > count as usize => diagnostics.push(Diagnostic {
+            level: Level::Error,
+            path,
+            issue: Issue::TooManySauRegions(config.regions.len(), count),
+        }),
+        _ => {}
+    }
+    diagnostics
+}
+
+/// Calls `f` on every register as declared, without expanding arrays,
+/// along with its dotted path and effective properties
+fn for_each_register<'a>(
+    device: &'a Device,
+    f: &mut dyn FnMut(String, RegisterProperties, &'a Register),
+) {
+    fn walk<'a>(
+        children: &'a [RegisterCluster],
+        path: &str,
+        properties: RegisterProperties,
+        f: &mut dyn FnMut(String, RegisterProperties, &'a Register),
+    ) {
+        for rc in children {
+            match rc {
+                RegisterCluster::Register(r) => f(
+                    format!("{}.{}", path, r.name),
+                    r.properties().inherit(&properties),
+                    r,
+                ),
+                RegisterCluster::Cluster(c) => walk(
+                    &c.children,
+                    &format!("{}.{}", path, c.name),
+                    c.default_register_properties.inherit(&properties),
+                    f,
+                ),
+            }
+        }
+    }
+
+    for p in &device.peripherals {
+        if let Some(registers) = &p.registers {
+            let properties = p
+                .default_register_properties
+                .inherit(&device.default_register_properties);
+            walk(registers, &p.name, properties, f);
+        }
+    }
+}
+
+/// Expands the fields of a register, reporting arrays whose bit offsets do not fit in 32 bits
+fn expand_fields(
+    path: &str,
+    register: &Register,
+    diagnostics: &mut Vec<Diagnostic>,
+) -> Vec<FieldInfo> {
+    let mut fields = Vec::new();
+    for f in register.fields.iter().flatten() {
+        match f.expand() {
+            Ok(infos) => fields.extend(infos),
+            Err(e) => diagnostics.push(array_overflow(format!("{}.{}", path, f.name), &e)),
+        }
+    }
+    fields
+}
+
+/// Error diagnostic for an array that could not be expanded
+fn array_overflow(path: String, error: &anyhow::Error) -> Diagnostic {
+    Diagnostic {
+        level: Level::Error,
+        path,
+        issue: Issue::ArrayOverflow(format!("{:#}", error)),
+    }
+}
+
+/// Most significant bit of a non-empty bit range, which may lie past bit 31
+fn msb(bit_range: &BitRange) -> u64 {
+    u64::from(bit_range.offset) + u64::from(bit_range.width) - 1
+}
+
+/// Whether two registers are declared as alternates of each other
+///
+/// Registers of different alternate groups, the default one included, are alternates.
+/// Registers of the same group are not.
+fn is_alternate(a: &RegisterItem, b: &RegisterItem) -> bool {
+    a.info.alternate_group != b.info.alternate_group
+        || a.info.alternate_register.as_ref() == Some(&b.info.name)
+        || b.info.alternate_register.as_ref() == Some(&a.info.name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::svd::field::Field;
+
+    #[test]
+    fn memory_map_overlaps() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <size>32</size>
+                <peripherals>
+                    <peripheral>
+                        <name>A</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <addressBlock>
+                            <offset>0</offset>
+                            <size>0x10</size>
+                            <usage>registers</usage>
+                        </addressBlock>
+                        <addressBlock>
+                            <offset>0x100</offset>
+                            <size>0x100</size>
+                            <usage>buffer</usage>
+                        </addressBlock>
+                        <addressBlock>
+                            <offset>0x20</offset>
+                            <size>0x20</size>
+                            <usage>reserved</usage>
+                        </addressBlock>
+                        <registers>
+                            <register>
+                                <name>RSVD</name>
+                                <addressOffset>0x20</addressOffset>
+                            </register>
+                            <register>
+                                <name>FIFO</name>
+                                <addressOffset>0x1fc</addressOffset>
+                            </register>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                            <register>
+                                <name>CR_ALT</name>
+                                <alternateRegister>CR</alternateRegister>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                            <register>
+                                <name>SR</name>
+                                <addressOffset>0x2</addressOffset>
+                                <size>16</size>
+                            </register>
+                            <register>
+                                <name>DR</name>
+                                <addressOffset>0x10</addressOffset>
+                            </register>
+                        </registers>
+                    </peripheral>
+                    <peripheral>
+                        <name>B</name>
+                        <baseAddress>0x40000008</baseAddress>
+                        <addressBlock>
+                            <offset>0</offset>
+                            <size>0x10</size>
+                            <usage>registers</usage>
+                        </addressBlock>
+                    </peripheral>
+                    <peripheral>
+                        <name>A_ALT</name>
+                        <alternatePeripheral>A</alternatePeripheral>
+                        <baseAddress>0x40000000</baseAddress>
+                        <addressBlock>
+                            <offset>0</offset>
+                            <size>0x4</size>
+                            <usage>registers</usage>
+                        </addressBlock>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let diagnostics: Vec<_> = memory_map(&device).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diagnostics,
+            [
+                "A.SR: Overlaps register `A.CR`",
+                "A.SR: Overlaps register `A.CR_ALT`",
+                "A.DR: Lies outside every address block of its peripheral",
+                "A.RSVD: Lies outside every address block of its peripheral",
+                "B: Overlaps peripheral `A`",
+            ]
+        );
+    }
+
+    #[test]
+    fn alternate_groups() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <size>32</size>
+                <peripherals>
+                    <peripheral>
+                        <name>TIM</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>CCMR</name>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                            <register>
+                                <name>CCMR_INPUT</name>
+                                <alternateGroup>INPUT</alternateGroup>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                            <register>
+                                <name>CCMR_FILTER</name>
+                                <alternateGroup>INPUT</alternateGroup>
+                                <addressOffset>0x2</addressOffset>
+                                <size>16</size>
+                            </register>
+                            <register>
+                                <name>CCMR_OUTPUT</name>
+                                <alternateGroup>OUTPUT</alternateGroup>
+                                <addressOffset>0x0</addressOffset>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let diagnostics: Vec<_> = memory_map(&device).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diagnostics,
+            ["TIM.CCMR_FILTER: Overlaps register `TIM.CCMR_INPUT`"]
+        );
+    }
+
+    #[test]
+    fn field_layout() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <peripherals>
+                    <peripheral>
+                        <name>P</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x0</addressOffset>
+                                <size>16</size>
+                                <fields>
+                                    <field>
+                                        <name>EN</name>
+                                        <bitRange>[3:0]</bitRange>
+                                    </field>
+                                    <field>
+                                        <name>MODE</name>
+                                        <bitRange>[5:2]</bitRange>
+                                    </field>
+                                    <field>
+                                        <name>EN</name>
+                                        <bitOffset>8</bitOffset>
+                                        <bitWidth>0</bitWidth>
+                                    </field>
+                                    <field>
+                                        <name>DATA</name>
+                                        <bitRange>[40:33]</bitRange>
+                                    </field>
+                                    <field>
+                                        <name>LAST</name>
+                                        <bitOffset>0xffffffff</bitOffset>
+                                        <bitWidth>2</bitWidth>
+                                    </field>
+                                </fields>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let diagnostics: Vec<_> = fields(&device).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diagnostics,
+            [
+                "P.CR.EN: Duplicate name",
+                "P.CR.EN: Zero-width field",
+                "P.CR.DATA: Bits [40:33] exceed the 16-bit register",
+                "P.CR.LAST: Bits [4294967296:4294967295] exceed the 16-bit register",
+                "P.CR.MODE: Overlaps field `EN`",
+            ]
+        );
+        // The other checks go past bit 31 without overflowing either
+        assert_eq!(crate::validate::device(&device).len(), diagnostics.len());
+    }
+
+    #[test]
+    fn array_overflow() {
+        let mut device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <peripherals>
+                    <peripheral>
+                        <name>P</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>BUF%s</name>
+                                <addressOffset>0x0</addressOffset>
+                                <dim>3</dim>
+                                <dimIncrement>4</dimIncrement>
+                            </register>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x10</addressOffset>
+                                <fields>
+                                    <field>
+                                        <name>EN%s</name>
+                                        <bitOffset>0</bitOffset>
+                                        <bitWidth>1</bitWidth>
+                                        <dim>3</dim>
+                                        <dimIncrement>1</dimIncrement>
+                                    </field>
+                                </fields>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+        // Arrays edited in code are not checked by the parser
+        let registers = device.peripherals[0].registers.as_mut().unwrap();
+        for rc in registers.iter_mut() {
+            if let RegisterCluster::Register(r) = rc {
+                match r {
+                    Register::Array(_, array_info) => array_info.dim_increment = 0x8000_0000,
+                    Register::Single(info) => match &mut info.fields.as_mut().unwrap()[0] {
+                        Field::Array(_, array_info) => array_info.dim_increment = 0x8000_0000,
+                        Field::Single(_) => panic!(),
+                    },
+                }
+            }
+        }
+
+        assert_eq!(
+            memory_map(&device)
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            ["P: In register `P.BUF%s`: Array of 3 elements 0x80000000 apart from offset 0x0 runs past 32 bits"]
+        );
+        assert_eq!(
+            fields(&device)
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<_>>(),
+            ["P.CR.EN%s: Array of 3 elements 0x80000000 apart from offset 0x0 runs past 32 bits"]
+        );
+    }
+
+    #[test]
+    fn enumerated_value_consistency() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <peripherals>
+                    <peripheral>
+                        <name>P</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x0</addressOffset>
+                                <fields>
+                                    <field>
+                                        <name>MODE</name>
+                                        <bitRange>[1:0]</bitRange>
+                                        <enumeratedValues>
+                                            <name>MODE_R</name>
+                                            <usage>read</usage>
+                                            <enumeratedValue>
+                                                <name>OFF</name>
+                                                <value>0</value>
+                                            </enumeratedValue>
+                                            <enumeratedValue>
+                                                <name>ON</name>
+                                                <value>1</value>
+                                            </enumeratedValue>
+                                            <enumeratedValue>
+                                                <name>ON</name>
+                                                <value>4</value>
+                                            </enumeratedValue>
+                                            <enumeratedValue>
+                                                <name>ENABLED</name>
+                                                <value>1</value>
+                                            </enumeratedValue>
+                                            <enumeratedValue>
+                                                <name>ODD</name>
+                                                <value>#x1</value>
+                                            </enumeratedValue>
+                                        </enumeratedValues>
+                                        <enumeratedValues>
+                                            <name>MODE_RW</name>
+                                            <enumeratedValue>
+                                                <name>A</name>
+                                                <isDefault>true</isDefault>
+                                            </enumeratedValue>
+                                            <enumeratedValue>
+                                                <name>B</name>
+                                                <isDefault>true</isDefault>
+                                            </enumeratedValue>
+                                        </enumeratedValues>
+                                    </field>
+                                </fields>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let diagnostics: Vec<_> = enumerated_values(&device)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            diagnostics,
+            [
+                "P.CR.MODE.MODE_R.ON: Duplicate name",
+                "P.CR.MODE.MODE_R.ON: Value 0x4 does not fit in 2 bits",
+                "P.CR.MODE.MODE_R.ENABLED: Same value as `ON`",
+                "P.CR.MODE.MODE_R.ODD: Same value as `ON`",
+                "P.CR.MODE.MODE_R: Covers 2 of 4 possible values and has no default",
+                "P.CR.MODE.MODE_RW: More than one default value",
+                "P.CR.MODE: More than one set of enumerated values for reading",
+            ]
+        );
+    }
+
+    #[test]
+    fn header_enum_name_uniqueness() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <peripherals>
+                    <peripheral>
+                        <name>P</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x0</addressOffset>
+                                <fields>
+                                    <field>
+                                        <name>EN</name>
+                                        <bitRange>[0:0]</bitRange>
+                                        <enumeratedValues>
+                                            <name>EN</name>
+                                            <headerEnumName>State</headerEnumName>
+                                            <enumeratedValue>
+                                                <name>OFF</name>
+                                                <value>0</value>
+                                            </enumeratedValue>
+                                            <enumeratedValue>
+                                                <name>ON</name>
+                                                <value>1</value>
+                                            </enumeratedValue>
+                                        </enumeratedValues>
+                                    </field>
+                                    <field>
+                                        <name>LOCK</name>
+                                        <bitRange>[1:1]</bitRange>
+                                        <enumeratedValues derivedFrom=\"EN\">
+                                            <headerEnumName>State</headerEnumName>
+                                        </enumeratedValues>
+                                    </field>
+                                </fields>
+                            </register>
+                            <register>
+                                <name>SR</name>
+                                <addressOffset>0x4</addressOffset>
+                                <fields>
+                                    <field>
+                                        <name>BUSY</name>
+                                        <bitRange>[0:0]</bitRange>
+                                        <enumeratedValues>
+                                            <headerEnumName>State</headerEnumName>
+                                            <enumeratedValue>
+                                                <name>IDLE</name>
+                                                <isDefault>true</isDefault>
+                                            </enumeratedValue>
+                                        </enumeratedValues>
+                                    </field>
+                                </fields>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let diagnostics: Vec<_> = enumerated_values(&device)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            diagnostics,
+            ["P.SR.BUSY: Header enum name `State` is also used by `P.CR.EN`"]
+        );
+    }
+
+    #[test]
+    fn reset_value_coherence() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <size>16</size>
+                <resetValue>0xffffffff</resetValue>
+                <resetMask>0xffff</resetMask>
+                <peripherals>
+                    <peripheral>
+                        <name>P</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <registers>
+                            <register>
+                                <name>CR</name>
+                                <addressOffset>0x0</addressOffset>
+                                <resetValue>0x10006</resetValue>
+                                <resetMask>0x1fff0</resetMask>
+                            </register>
+                            <register>
+                                <name>DR</name>
+                                <addressOffset>0x4</addressOffset>
+                                <size>8</size>
+                            </register>
+                            <register>
+                                <name>SR</name>
+                                <addressOffset>0x2</addressOffset>
+                                <resetValue>0x0312</resetValue>
+                                <resetMask>0x0fff</resetMask>
+                                <writeConstraint>
+                                    <range>
+                                        <minimum>0</minimum>
+                                        <maximum>0xff</maximum>
+                                    </range>
+                                </writeConstraint>
+                                <fields>
+                                    <field>
+                                        <name>MODE</name>
+                                        <bitRange>[1:0]</bitRange>
+                                        <writeConstraint>
+                                            <useEnumeratedValues>true</useEnumeratedValues>
+                                        </writeConstraint>
+                                        <enumeratedValues>
+                                            <enumeratedValue>
+                                                <name>OFF</name>
+                                                <value>0</value>
+                                            </enumeratedValue>
+                                            <enumeratedValue>
+                                                <name>ON</name>
+                                                <value>1</value>
+                                            </enumeratedValue>
+                                        </enumeratedValues>
+                                    </field>
+                                    <field>
+                                        <name>LEVEL</name>
+                                        <bitRange>[7:4]</bitRange>
+                                        <writeConstraint>
+                                            <range>
+                                                <minimum>2</minimum>
+                                                <maximum>8</maximum>
+                                            </range>
+                                        </writeConstraint>
+                                    </field>
+                                    <field>
+                                        <name>HIGH</name>
+                                        <bitRange>[15:12]</bitRange>
+                                        <writeConstraint>
+                                            <range>
+                                                <minimum>1</minimum>
+                                                <maximum>2</maximum>
+                                            </range>
+                                        </writeConstraint>
+                                    </field>
+                                </fields>
+                            </register>
+                        </registers>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let diagnostics: Vec<_> = reset_values(&device)
+            .iter()
+            .map(|d| (d.level, d.to_string()))
+            .collect();
+        assert_eq!(
+            diagnostics,
+            [
+                (
+                    Level::Error,
+                    "P.CR: Reset mask 0x1fff0 does not fit in 16 bits".to_string()
+                ),
+                (
+                    Level::Error,
+                    "P.CR: Reset value 0x10006 does not fit in 16 bits".to_string()
+                ),
+                (
+                    Level::Error,
+                    "P.CR: Reset value 0x10006 has bits set outside reset mask 0x1fff0".to_string()
+                ),
+                (
+                    Level::Warning,
+                    "P.SR: Reset value 0x312 is outside the write range 0x0..=0xff".to_string()
+                ),
+                (
+                    Level::Warning,
+                    "P.SR.MODE: Reset value 0x2 is not one of the enumerated values".to_string()
+                ),
+                (
+                    Level::Warning,
+                    "P.SR.LEVEL: Reset value 0x1 is outside the write range 0x2..=0x8".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn interrupt_conflicts() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <cpu>
+                    <name>CM0</name>
+                    <revision>r0p0</revision>
+                    <endian>little</endian>
+                    <mpuPresent>false</mpuPresent>
+                    <fpuPresent>false</fpuPresent>
+                    <nvicPrioBits>2</nvicPrioBits>
+                    <vendorSystickConfig>false</vendorSystickConfig>
+                    <deviceNumInterrupts>3</deviceNumInterrupts>
+                </cpu>
+                <peripherals>
+                    <peripheral>
+                        <name>TIM1</name>
+                        <baseAddress>0x40000000</baseAddress>
+                        <interrupt>
+                            <name>TIM1</name>
+                            <value>2</value>
+                        </interrupt>
+                    </peripheral>
+                    <peripheral derivedFrom=\"TIM1\">
+                        <name>TIM2</name>
+                        <baseAddress>0x40000400</baseAddress>
+                    </peripheral>
+                    <peripheral>
+                        <name>DMA</name>
+                        <baseAddress>0x40001000</baseAddress>
+                        <interrupt>
+                            <name>DMA</name>
+                            <value>2</value>
+                        </interrupt>
+                        <interrupt>
+                            <name>TIM1</name>
+                            <value>3</value>
+                        </interrupt>
+                    </peripheral>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let diagnostics: Vec<_> = interrupts(&device).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diagnostics,
+            [
+                "DMA.DMA: Same value as `TIM1`",
+                "DMA.TIM1: Also declared with value 2",
+                "DMA.TIM1: Value 3 exceeds the 3 interrupts of the device",
+            ]
+        );
+    }
+
+    #[test]
+    fn sau_regions() {
+        let device = crate::parse(
+            "
+            <device>
+                <name>DEV</name>
+                <cpu>
+                    <name>CM33</name>
+                    <revision>r0p4</revision>
+                    <endian>little</endian>
+                    <mpuPresent>false</mpuPresent>
+                    <fpuPresent>false</fpuPresent>
+                    <nvicPrioBits>3</nvicPrioBits>
+                    <vendorSystickConfig>false</vendorSystickConfig>
+                    <sauNumRegions>2</sauNumRegions>
+                    <sauRegionsConfig enabled=\"true\">
+                        <region name=\"FLASH_NS\">
+                            <base>0x00040000</base>
+                            <limit>0x0007FFFF</limit>
+                            <access>n</access>
+                        </region>
+                        <region>
+                            <base>0x20010000</base>
+                            <limit>0x20000000</limit>
+                            <access>n</access>
+                        </region>
+                        <region name=\"NSC\">
+                            <base>0x10000010</base>
+                            <limit>0x10000100</limit>
+                            <access>c</access>
+                        </region>
+                    </sauRegionsConfig>
+                </cpu>
+                <peripherals>
+                </peripherals>
+            </device>
+            ",
+        )
+        .unwrap();
+
+        let diagnostics: Vec<_> = sau(&device).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diagnostics,
+            [
+                "CM33.sauRegionsConfig.1: Base 0x20010000 is above limit 0x20000000",
+                "CM33.sauRegionsConfig.NSC: Base 0x10000010 or limit 0x10000100 is not aligned on 32 bytes",
+                "CM33.sauRegionsConfig: 3 regions exceed the 2 regions of the SAU",
+            ]
+        );
+    }
+}